@@ -0,0 +1,477 @@
+use crate::bus::{CameraEvent, EventBus};
+use crate::event::{Event, EventKind};
+use crate::media_index::{MediaIndex, MediaKind};
+use crate::presence::Occupancy;
+use crate::recorder::{self, RecordingSource};
+use crate::reolink::ReolinkClient;
+use crate::storage::{MediaStorage, SharedMediaStore};
+use crate::{HEIGHT, WIDTH};
+use chrono::NaiveTime;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    Record {
+        #[serde(default = "default_duration_secs")]
+        duration_secs: u64,
+    },
+    Ntfy {
+        #[serde(default = "default_ntfy_server")]
+        server: String,
+        topic: String,
+        message: Option<String>,
+    },
+    Spotlight {
+        target: Option<String>,
+        #[serde(default = "default_duration_secs")]
+        duration_secs: u64,
+    },
+    Siren {
+        target: Option<String>,
+    },
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RuleConfig {
+    pub event: EventKind,
+    pub camera: Option<String>,
+    pub start: Option<NaiveTime>,
+    pub end: Option<NaiveTime>,
+    #[serde(default)]
+    pub presence: Option<Occupancy>,
+    pub actions: Vec<RuleAction>,
+}
+
+fn default_duration_secs() -> u64 {
+    30
+}
+
+pub fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+impl RuleConfig {
+    fn matches(&self, event: &Event, time: NaiveTime, occupancy: Option<Occupancy>) -> bool {
+        if self.event != event.kind {
+            return false;
+        }
+        if self.presence.is_some() && self.presence != occupancy {
+            return false;
+        }
+        if self
+            .camera
+            .as_ref()
+            .is_some_and(|camera| *camera != event.camera)
+        {
+            return false;
+        }
+
+        match (self.start, self.end) {
+            (Some(start), Some(end)) if start <= end => time >= start && time < end,
+            (Some(start), Some(end)) => time >= start || time < end,
+            _ => true,
+        }
+    }
+}
+
+pub struct Automation {
+    rules: Vec<RuleConfig>,
+    clients: HashMap<String, ReolinkClient>,
+    cameras: HashMap<String, RecordingSource>,
+    media_store: SharedMediaStore,
+    use_tcp_for_rtsp: bool,
+    media_index: MediaIndex,
+    bus: EventBus,
+}
+
+impl Automation {
+    pub fn new(
+        rules: Vec<RuleConfig>,
+        clients: HashMap<String, ReolinkClient>,
+        cameras: HashMap<String, RecordingSource>,
+        media_store: SharedMediaStore,
+        use_tcp_for_rtsp: bool,
+        media_index: MediaIndex,
+        bus: EventBus,
+    ) -> Self {
+        Automation {
+            rules,
+            clients,
+            cameras,
+            media_store,
+            use_tcp_for_rtsp,
+            media_index,
+            bus,
+        }
+    }
+
+    pub fn spawn(self) {
+        if self.rules.is_empty() {
+            return;
+        }
+
+        let events = self.bus.subscribe();
+        thread::spawn(move || {
+            let mut occupancy = None;
+            for event in events {
+                match event {
+                    CameraEvent::Detected(event) => self.handle(&event, occupancy),
+                    CameraEvent::Presence(current) => occupancy = Some(current),
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    fn handle(&self, event: &Event, occupancy: Option<Occupancy>) {
+        let now = chrono::Local::now().time();
+
+        for rule in self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches(event, now, occupancy))
+        {
+            for action in &rule.actions {
+                self.run(action, event);
+            }
+        }
+    }
+
+    fn run(&self, action: &RuleAction, event: &Event) {
+        match action {
+            RuleAction::Record { duration_secs } => self.record(event, *duration_secs),
+            RuleAction::Ntfy {
+                server,
+                topic,
+                message,
+            } => send_ntfy(server, topic, message.as_deref(), event),
+            RuleAction::Spotlight {
+                target,
+                duration_secs,
+            } => {
+                if let Some((camera, client)) = self.client_for(target, event) {
+                    flash_spotlight(camera, client, *duration_secs);
+                }
+            }
+            RuleAction::Siren { target } => {
+                if let Some((camera, client)) = self.client_for(target, event) {
+                    sound_siren(camera, client);
+                }
+            }
+            RuleAction::Command { program, args } => self.run_command(program, args, event),
+        }
+    }
+
+    fn run_command(&self, program: &str, args: &[String], event: &Event) {
+        let program = program.to_string();
+        let args = args.to_vec();
+        let camera = event.camera.clone();
+        let kind = event.kind;
+        let url = self
+            .cameras
+            .get(&event.camera)
+            .map(|source| source.url.clone());
+        let media_store = self.media_store.clone();
+        let use_tcp_for_rtsp = self.use_tcp_for_rtsp;
+        let media_index = self.media_index.clone();
+        let bus = self.bus.clone();
+
+        thread::spawn(move || {
+            let now = chrono::Local::now();
+            let snapshot = if args.iter().any(|arg| arg.contains("{snapshot}")) {
+                url.and_then(|url| {
+                    save_snapshot(
+                        &url,
+                        media_store.as_ref(),
+                        &camera,
+                        kind,
+                        use_tcp_for_rtsp,
+                        &media_index,
+                        &bus,
+                    )
+                })
+                .unwrap_or_default()
+            } else {
+                String::new()
+            };
+
+            let args: Vec<String> = args
+                .iter()
+                .map(|arg| {
+                    arg.replace("{camera}", &camera)
+                        .replace("{event}", kind.name())
+                        .replace("{timestamp}", &now.to_rfc3339())
+                        .replace("{snapshot}", &snapshot)
+                })
+                .collect();
+
+            match std::process::Command::new(&program).args(&args).status() {
+                Ok(status) if !status.success() => {
+                    eprintln!("Le script {} a échoué ({})", program, status)
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Impossible de lancer le script {} : {}", program, e),
+            }
+        });
+    }
+
+    fn client_for(
+        &self,
+        target: &Option<String>,
+        event: &Event,
+    ) -> Option<(String, ReolinkClient)> {
+        let camera = target.as_ref().unwrap_or(&event.camera);
+        match self.clients.get(camera) {
+            Some(client) => Some((camera.clone(), client.clone())),
+            None => {
+                eprintln!("Règle ignorée : {} n'a pas d'accès Reolink", camera);
+                None
+            }
+        }
+    }
+
+    fn record(&self, event: &Event, duration_secs: u64) {
+        let Some(source) = self.cameras.get(&event.camera).cloned() else {
+            eprintln!(
+                "Enregistrement impossible : caméra {} inconnue",
+                event.camera
+            );
+            return;
+        };
+
+        let camera = event.camera.clone();
+        let kind = event.kind;
+        let media_store = self.media_store.clone();
+        let use_tcp_for_rtsp = self.use_tcp_for_rtsp;
+        let media_index = self.media_index.clone();
+
+        thread::spawn(move || {
+            let now = chrono::Local::now();
+            let filename = media_store.file_name(&camera, Some(kind), now, "mp4");
+
+            match media_store.write(&filename, |path| {
+                recorder::record_clip(
+                    &source,
+                    path,
+                    Duration::from_secs(duration_secs),
+                    use_tcp_for_rtsp,
+                )
+                .map_err(|e| e.to_string())
+            }) {
+                Ok(path) => media_index.record_media(
+                    now.naive_local(),
+                    &camera,
+                    MediaKind::Clip,
+                    &path,
+                    Some(kind),
+                ),
+                Err(e) => eprintln!("Erreur lors de l'enregistrement de {} : {}", camera, e),
+            }
+        });
+    }
+}
+
+fn save_snapshot(
+    url: &str,
+    media_store: &MediaStorage,
+    camera: &str,
+    kind: EventKind,
+    use_tcp_for_rtsp: bool,
+    media_index: &MediaIndex,
+    bus: &EventBus,
+) -> Option<String> {
+    let data = match recorder::grab_frame(url, use_tcp_for_rtsp) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Impossible de capturer une image de {} : {}", camera, e);
+            return None;
+        }
+    };
+
+    let now = chrono::Local::now();
+    let filename = media_store.file_name(camera, Some(kind), now, "png");
+    let image = image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(WIDTH, HEIGHT, data)?;
+    let path = match media_store.write(&filename, |path| {
+        image.save(path).map_err(|e| e.to_string())
+    }) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Erreur lors de la sauvegarde de l'image : {}", e);
+            bus.publish(CameraEvent::SnapshotFailed {
+                camera: camera.to_string(),
+                error: e,
+            });
+            return None;
+        }
+    };
+
+    media_index.record_media(
+        now.naive_local(),
+        camera,
+        MediaKind::Snapshot,
+        &path,
+        Some(kind),
+    );
+    bus.publish(CameraEvent::SnapshotSaved {
+        camera: camera.to_string(),
+        path: path.clone(),
+    });
+    Some(path.to_string_lossy().to_string())
+}
+
+fn send_ntfy(server: &str, topic: &str, message: Option<&str>, event: &Event) {
+    let title = format!("{} ({})", event.kind.label(), event.camera);
+    let body = message
+        .map(|message| message.replace("{camera}", &event.camera))
+        .unwrap_or_else(|| title.clone());
+
+    let server = server.to_string();
+    let topic = topic.to_string();
+    thread::spawn(move || {
+        if let Err(e) = post_ntfy(&server, &topic, &title, body) {
+            eprintln!("Erreur lors de l'envoi de la notification ntfy : {}", e);
+        }
+    });
+}
+
+pub fn post_ntfy(server: &str, topic: &str, title: &str, body: String) -> Result<(), ureq::Error> {
+    let url = format!("{}/{}", server.trim_end_matches('/'), topic);
+    ureq::post(&url)
+        .header("Title", title)
+        .send(body)
+        .map(|_| ())
+}
+
+fn flash_spotlight(camera: String, client: ReolinkClient, duration_secs: u64) {
+    thread::spawn(move || {
+        let result = client.set_spotlight(true).and_then(|_| {
+            thread::sleep(Duration::from_secs(duration_secs));
+            client.set_spotlight(false)
+        });
+
+        if let Err(e) = result {
+            eprintln!("Impossible de piloter le projecteur de {} : {}", camera, e);
+        }
+    });
+}
+
+pub fn sound_siren(camera: String, client: ReolinkClient) {
+    thread::spawn(move || {
+        if let Err(e) = client.sound_siren() {
+            eprintln!("Impossible de déclencher la sirène de {} : {}", camera, e);
+        }
+    });
+}
+
+pub fn set_spotlight(camera: String, client: ReolinkClient, on: bool) {
+    thread::spawn(move || {
+        if let Err(e) = client.set_spotlight(on) {
+            eprintln!("Impossible de piloter le projecteur de {} : {}", camera, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media_index::MediaFilter;
+    use crate::storage::{self, StorageConfig};
+    use crate::testing::{self, write_fixture};
+
+    fn rule(camera: Option<&str>, start: Option<&str>, end: Option<&str>) -> RuleConfig {
+        RuleConfig {
+            event: EventKind::Person,
+            camera: camera.map(str::to_string),
+            start: start.map(|time| time.parse().unwrap()),
+            end: end.map(|time| time.parse().unwrap()),
+            presence: None,
+            actions: Vec::new(),
+        }
+    }
+
+    fn event(camera: &str, kind: EventKind) -> Event {
+        Event {
+            camera: camera.to_string(),
+            kind,
+            details: None,
+        }
+    }
+
+    #[test]
+    fn rule_matches_event_kind_and_camera() {
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        let rule = rule(Some("Jardin"), None, None);
+
+        assert!(rule.matches(&event("Jardin", EventKind::Person), noon, None));
+        assert!(!rule.matches(&event("Jardin", EventKind::Motion), noon, None));
+        assert!(!rule.matches(&event("Portail", EventKind::Person), noon, None));
+
+        let away = RuleConfig {
+            presence: Some(Occupancy::Away),
+            ..rule
+        };
+        let person = event("Jardin", EventKind::Person);
+        assert!(away.matches(&person, noon, Some(Occupancy::Away)));
+        assert!(!away.matches(&person, noon, Some(Occupancy::Home)));
+        assert!(!away.matches(&person, noon, None));
+    }
+
+    #[test]
+    fn rule_time_window_can_span_midnight() {
+        let rule = rule(None, Some("22:00:00"), Some("06:00:00"));
+        let person = event("Jardin", EventKind::Person);
+
+        assert!(rule.matches(&person, NaiveTime::from_hms_opt(23, 30, 0).unwrap(), None));
+        assert!(rule.matches(&person, NaiveTime::from_hms_opt(3, 0, 0).unwrap(), None));
+        assert!(!rule.matches(&person, NaiveTime::from_hms_opt(12, 0, 0).unwrap(), None));
+    }
+
+    #[test]
+    fn snapshot_is_written_and_indexed() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source.y4m");
+        write_fixture(&source, 3);
+
+        let media_index = MediaIndex::open_in_memory().unwrap();
+        let bus = EventBus::default();
+        let events = bus.subscribe();
+
+        let media_store = storage::open(
+            &StorageConfig::Local,
+            dir.path(),
+            &storage::default_filename_template(),
+        );
+        let filename = save_snapshot(
+            source.to_str().unwrap(),
+            media_store.as_ref(),
+            "Jardin",
+            EventKind::Person,
+            false,
+            &media_index,
+            &bus,
+        )
+        .expect("capture non enregistrée");
+
+        assert!(std::path::Path::new(&filename).exists());
+
+        let media = media_index.media(&MediaFilter::default());
+        assert_eq!(media.len(), 1);
+        assert_eq!(media[0].camera, "Jardin");
+        assert_eq!(media[0].event, Some(EventKind::Person));
+
+        let saved = testing::wait_for(&events, Duration::from_secs(1), |event| match event {
+            CameraEvent::SnapshotSaved { path, .. } => Some(path),
+            _ => None,
+        });
+        assert_eq!(saved, Some(filename.into()));
+    }
+}
@@ -0,0 +1,195 @@
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use rusqlite::Connection;
+use serde::Deserialize;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+const INDEX_FILE: &str = "index.sqlite";
+
+#[derive(Deserialize)]
+struct BundleConfig {
+    config: BundleCaptureConfig,
+}
+
+#[derive(Deserialize)]
+struct BundleCaptureConfig {
+    capture_path: String,
+}
+
+fn capture_path(config_path: &Path) -> Result<PathBuf, String> {
+    let content = std::fs::read_to_string(config_path)
+        .map_err(|e| format!("Impossible de lire {} : {}", config_path.display(), e))?;
+    let config: BundleConfig = toml::from_str(&content)
+        .map_err(|e| format!("Impossible de parser {} : {}", config_path.display(), e))?;
+    let base = config_path.parent().unwrap_or(Path::new(""));
+    Ok(base.join(config.config.capture_path))
+}
+
+fn snapshot_index(index_path: &Path, snapshot_path: &Path) -> rusqlite::Result<()> {
+    let conn = Connection::open(index_path)?;
+    conn.execute("VACUUM INTO ?1", [snapshot_path.to_string_lossy()])?;
+    Ok(())
+}
+
+pub fn create(archive: &Path, config_path: &Path, state_path: &Path) -> Result<(), String> {
+    let index_path = capture_path(config_path)?.join(INDEX_FILE);
+
+    let file = File::create(archive)
+        .map_err(|e| format!("Impossible de créer {} : {}", archive.display(), e))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let mut add = |path: &Path, name: &str| {
+        builder
+            .append_path_with_name(path, name)
+            .map_err(|e| format!("Impossible d'ajouter {} : {}", path.display(), e))
+    };
+    add(config_path, "config.toml")?;
+    if state_path.exists() {
+        add(state_path, "state.toml")?;
+    }
+
+    if index_path.exists() {
+        let snapshot = std::env::temp_dir().join(format!("security-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&snapshot);
+        let result = snapshot_index(&index_path, &snapshot)
+            .map_err(|e| format!("Impossible de copier l'index des médias : {}", e))
+            .and_then(|()| add(&snapshot, INDEX_FILE));
+        let _ = std::fs::remove_file(&snapshot);
+        result?;
+    }
+
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| format!("Impossible de finaliser {} : {}", archive.display(), e))?;
+    Ok(())
+}
+
+fn replace_file(entry: &mut tar::Entry<impl std::io::Read>, path: &Path) -> Result<(), String> {
+    if path.exists() {
+        let backup = path.with_extension(format!(
+            "{}.bak",
+            path.extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or_default()
+        ));
+        std::fs::rename(path, &backup)
+            .map_err(|e| format!("Impossible de sauvegarder {} : {}", path.display(), e))?;
+    }
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Impossible de créer {} : {}", parent.display(), e))?;
+    }
+    entry
+        .unpack(path)
+        .map_err(|e| format!("Impossible d'écrire {} : {}", path.display(), e))?;
+    Ok(())
+}
+
+pub fn restore(archive: &Path, config_path: &Path, state_path: &Path) -> Result<(), String> {
+    let open = || {
+        File::open(archive)
+            .map(|file| tar::Archive::new(GzDecoder::new(file)))
+            .map_err(|e| format!("Impossible d'ouvrir {} : {}", archive.display(), e))
+    };
+
+    let mut archive_reader = open()?;
+    let entries = archive_reader
+        .entries()
+        .map_err(|e| format!("Archive invalide : {}", e))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Archive invalide : {}", e))?;
+        let name = entry
+            .path()
+            .map_err(|e| format!("Archive invalide : {}", e))?
+            .to_string_lossy()
+            .into_owned();
+        match name.as_str() {
+            "config.toml" => replace_file(&mut entry, config_path)?,
+            "state.toml" => replace_file(&mut entry, state_path)?,
+            _ => {}
+        }
+    }
+
+    let index_path = capture_path(config_path)?.join(INDEX_FILE);
+    let mut archive_reader = open()?;
+    let entries = archive_reader
+        .entries()
+        .map_err(|e| format!("Archive invalide : {}", e))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Archive invalide : {}", e))?;
+        if entry.path().is_ok_and(|path| path == Path::new(INDEX_FILE)) {
+            replace_file(&mut entry, &index_path)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn run(command: &str, args: &[String], config_path: &str, state_path: &str) {
+    let Some(archive) = args.first() else {
+        eprintln!("Usage : security {} <archive.tar.gz>", command);
+        std::process::exit(2);
+    };
+    let archive = Path::new(archive);
+    let (config_path, state_path) = (Path::new(config_path), Path::new(state_path));
+
+    let result = if command == "backup" {
+        create(archive, config_path, state_path)
+    } else {
+        restore(archive, config_path, state_path)
+    };
+    match result {
+        Ok(()) if command == "backup" => println!("Sauvegarde créée : {}", archive.display()),
+        Ok(()) => println!(
+            "Configuration restaurée depuis {} (anciens fichiers renommés en .bak)",
+            archive.display()
+        ),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restores_bundle_into_another_kiosk() {
+        let source = tempfile::tempdir().unwrap();
+        let captures = source.path().join("captures");
+        std::fs::create_dir_all(&captures).unwrap();
+        let config_path = source.path().join("config.toml");
+        std::fs::write(&config_path, "[config]\ncapture_path = \"captures\"\n").unwrap();
+        let state_path = source.path().join("state.toml");
+        std::fs::write(&state_path, "show_gallery = true\n").unwrap();
+        Connection::open(captures.join(INDEX_FILE))
+            .unwrap()
+            .execute_batch("CREATE TABLE events (id INTEGER PRIMARY KEY);")
+            .unwrap();
+
+        let archive = source.path().join("kiosk.tar.gz");
+        create(&archive, &config_path, &state_path).unwrap();
+
+        let target = tempfile::tempdir().unwrap();
+        let target_config = target.path().join("config.toml");
+        std::fs::write(&target_config, "ancienne configuration").unwrap();
+        restore(&archive, &target_config, &target.path().join("state.toml")).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&target_config).unwrap(),
+            std::fs::read_to_string(&config_path).unwrap()
+        );
+        assert_eq!(
+            std::fs::read_to_string(target.path().join("config.toml.bak")).unwrap(),
+            "ancienne configuration"
+        );
+        assert!(target.path().join("state.toml").exists());
+        assert!(target.path().join("captures").join(INDEX_FILE).exists());
+    }
+}
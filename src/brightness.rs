@@ -0,0 +1,169 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BrightnessBackend {
+    #[default]
+    Backlight,
+    Ddc,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BrightnessConfig {
+    #[serde(default)]
+    pub backend: BrightnessBackend,
+    pub device: Option<PathBuf>,
+    #[serde(default = "default_ddc_display")]
+    pub ddc_display: u8,
+    pub night: Option<f32>,
+    pub idle: Option<f32>,
+    #[serde(default = "default_idle_after_secs")]
+    pub idle_after_secs: u64,
+}
+
+fn default_ddc_display() -> u8 {
+    1
+}
+
+fn default_idle_after_secs() -> u64 {
+    30
+}
+
+impl BrightnessConfig {
+    fn target(&self, level: f32, night: bool, idle: bool) -> f32 {
+        let mut target = level;
+        if night && let Some(night) = self.night {
+            target = target.min(night);
+        }
+        if idle && let Some(idle) = self.idle {
+            target = target.min(idle);
+        }
+        target.clamp(0.05, 1.0)
+    }
+}
+
+pub struct Brightness {
+    config: BrightnessConfig,
+    level: f32,
+    applied: Option<f32>,
+    sender: Sender<f32>,
+}
+
+fn backlight_device(config: &BrightnessConfig) -> Option<PathBuf> {
+    if let Some(device) = &config.device {
+        return Some(device.clone());
+    }
+    std::fs::read_dir(BACKLIGHT_DIR)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .min()
+}
+
+fn set_backlight(device: &Path, level: f32) -> Result<(), String> {
+    let max: u32 = std::fs::read_to_string(device.join("max_brightness"))
+        .map_err(|e| e.to_string())?
+        .trim()
+        .parse()
+        .map_err(|e: std::num::ParseIntError| e.to_string())?;
+    let value = (level * max as f32).round() as u32;
+    std::fs::write(device.join("brightness"), value.to_string()).map_err(|e| e.to_string())
+}
+
+fn set_ddc(display: u8, level: f32) -> Result<(), String> {
+    let status = Command::new("ddcutil")
+        .args([
+            "--display",
+            &display.to_string(),
+            "setvcp",
+            "10",
+            &((level * 100.0).round() as u32).to_string(),
+        ])
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("ddcutil a échoué ({})", status))
+    }
+}
+
+impl Brightness {
+    pub fn spawn(config: BrightnessConfig, level: f32) -> Self {
+        let (sender, receiver) = mpsc::channel::<f32>();
+        let worker_config = config.clone();
+        thread::spawn(move || {
+            let device = backlight_device(&worker_config);
+            while let Ok(mut level) = receiver.recv() {
+                while let Ok(next) = receiver.try_recv() {
+                    level = next;
+                }
+                let result = match (worker_config.backend, &device) {
+                    (BrightnessBackend::Backlight, Some(device)) => set_backlight(device, level),
+                    (BrightnessBackend::Backlight, None) => {
+                        Err("aucun rétroéclairage trouvé".to_string())
+                    }
+                    (BrightnessBackend::Ddc, _) => set_ddc(worker_config.ddc_display, level),
+                };
+                if let Err(e) = result {
+                    eprintln!("Impossible de régler la luminosité : {}", e);
+                }
+            }
+        });
+
+        Brightness {
+            config,
+            level: level.clamp(0.05, 1.0),
+            applied: None,
+            sender,
+        }
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    pub fn set_level(&mut self, level: f32) {
+        self.level = level.clamp(0.05, 1.0);
+    }
+
+    pub fn update(&mut self, night: bool, idle_secs: u64) {
+        let idle = idle_secs >= self.config.idle_after_secs;
+        let target = self.config.target(self.level, night, idle);
+        if self
+            .applied
+            .is_some_and(|applied| (applied - target).abs() < 0.01)
+        {
+            return;
+        }
+        self.applied = Some(target);
+        let _ = self.sender.send(target);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dims_to_lowest_applicable_level() {
+        let config = BrightnessConfig {
+            backend: BrightnessBackend::Backlight,
+            device: None,
+            ddc_display: default_ddc_display(),
+            night: Some(0.4),
+            idle: Some(0.2),
+            idle_after_secs: default_idle_after_secs(),
+        };
+        assert_eq!(config.target(0.8, false, false), 0.8);
+        assert_eq!(config.target(0.8, true, false), 0.4);
+        assert_eq!(config.target(0.3, true, false), 0.3);
+        assert_eq!(config.target(0.8, true, true), 0.2);
+        assert_eq!(config.target(0.0, false, false), 0.05);
+    }
+}
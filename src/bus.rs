@@ -0,0 +1,61 @@
+use crate::decoder::{StatusUpdate, VideoFrame};
+use crate::doorbell::DoorbellHealth;
+use crate::event::{Event, RemoteCommand};
+use crate::onvif::AnalyticsObject;
+use crate::presence::Occupancy;
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone)]
+pub enum CameraEvent {
+    FrameReady(Arc<VideoFrame>),
+    Detected(Event),
+    StreamStatus(StatusUpdate),
+    SnapshotSaved {
+        camera: String,
+        path: PathBuf,
+    },
+    SnapshotFailed {
+        camera: String,
+        error: String,
+    },
+    Command(RemoteCommand),
+    DoorbellStatus(DoorbellHealth),
+    Analytics {
+        url: String,
+        objects: Vec<AnalyticsObject>,
+    },
+    Presence(Occupancy),
+}
+
+struct Subscriber {
+    sender: Sender<CameraEvent>,
+    filter: fn(&CameraEvent) -> bool,
+}
+
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl EventBus {
+    pub fn subscribe(&self) -> Receiver<CameraEvent> {
+        self.subscribe_filtered(|_| true)
+    }
+
+    pub fn subscribe_filtered(&self, filter: fn(&CameraEvent) -> bool) -> Receiver<CameraEvent> {
+        let (sender, receiver) = unbounded();
+        self.subscribers
+            .lock()
+            .unwrap()
+            .push(Subscriber { sender, filter });
+        receiver
+    }
+
+    pub fn publish(&self, event: CameraEvent) {
+        self.subscribers.lock().unwrap().retain(|subscriber| {
+            !(subscriber.filter)(&event) || subscriber.sender.send(event.clone()).is_ok()
+        });
+    }
+}
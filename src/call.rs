@@ -0,0 +1,143 @@
+use serde::Deserialize;
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CallConfig {
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    pub answer_command: Option<String>,
+    #[serde(default)]
+    pub answer_args: Vec<String>,
+    pub open_door_url: Option<String>,
+}
+
+fn default_timeout_secs() -> u64 {
+    45
+}
+
+pub struct Call {
+    pub camera: String,
+    config: CallConfig,
+    started: Instant,
+    talk: Option<Child>,
+}
+
+impl Call {
+    pub fn ring(camera: &str, config: CallConfig) -> Self {
+        Call {
+            camera: camera.to_string(),
+            config,
+            started: Instant::now(),
+            talk: None,
+        }
+    }
+
+    pub fn can_answer(&self) -> bool {
+        self.config.answer_command.is_some()
+    }
+
+    pub fn can_open_door(&self) -> bool {
+        self.config.open_door_url.is_some()
+    }
+
+    pub fn is_answered(&self) -> bool {
+        self.talk.is_some()
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.is_answered() {
+            return None;
+        }
+        Some(Duration::from_secs(self.config.timeout_secs).saturating_sub(self.started.elapsed()))
+    }
+
+    pub fn is_over(&mut self) -> bool {
+        match &mut self.talk {
+            Some(talk) => !matches!(talk.try_wait(), Ok(None)),
+            None => self
+                .remaining()
+                .is_some_and(|remaining| remaining.is_zero()),
+        }
+    }
+
+    pub fn answer(&mut self) {
+        let Some(command) = &self.config.answer_command else {
+            return;
+        };
+        let args: Vec<String> = self
+            .config
+            .answer_args
+            .iter()
+            .map(|arg| arg.replace("{camera}", &self.camera))
+            .collect();
+
+        match Command::new(command).args(&args).spawn() {
+            Ok(child) => {
+                println!("Appel de {} décroché", self.camera);
+                self.talk = Some(child);
+            }
+            Err(e) => eprintln!("Impossible de lancer l'interphone {} : {}", command, e),
+        }
+    }
+
+    pub fn hang_up(&mut self) {
+        if let Some(mut talk) = self.talk.take() {
+            let _ = talk.kill();
+            let _ = talk.wait();
+        }
+    }
+
+    pub fn open_door(&self) {
+        let Some(url) = self.config.open_door_url.clone() else {
+            return;
+        };
+        let camera = self.camera.clone();
+        thread::spawn(move || {
+            let agent = ureq::Agent::new_with_config(
+                ureq::Agent::config_builder()
+                    .timeout_global(Some(Duration::from_secs(5)))
+                    .build(),
+            );
+            match agent.get(&url).call() {
+                Ok(_) => println!("Porte ouverte depuis l'appel de {}", camera),
+                Err(e) => eprintln!("Impossible d'ouvrir la porte : {}", e),
+            }
+        });
+    }
+}
+
+impl Drop for Call {
+    fn drop(&mut self) {
+        self.hang_up();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unanswered_call_expires() {
+        let config = CallConfig {
+            timeout_secs: 0,
+            answer_command: None,
+            answer_args: Vec::new(),
+            open_door_url: None,
+        };
+        let mut call = Call::ring("Sonnette", config.clone());
+        assert!(call.is_over());
+        assert!(!call.can_answer());
+
+        let mut call = Call::ring(
+            "Sonnette",
+            CallConfig {
+                timeout_secs: 60,
+                ..config
+            },
+        );
+        assert!(!call.is_over());
+        assert!(call.remaining().unwrap() > Duration::from_secs(50));
+    }
+}
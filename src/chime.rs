@@ -0,0 +1,87 @@
+use chrono::NaiveTime;
+use serde::Deserialize;
+use std::thread;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ChimeConfig {
+    pub sound: String,
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    #[serde(default)]
+    pub volume: f32,
+}
+
+fn default_volume() -> f32 {
+    1.0
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+pub struct Chime {
+    config: ChimeConfig,
+}
+
+impl Chime {
+    pub fn new(config: ChimeConfig) -> Self {
+        Chime { config }
+    }
+
+    fn current_volume(&self) -> f32 {
+        let now = chrono::Local::now().time();
+        match &self.config.quiet_hours {
+            Some(quiet) if quiet.contains(now) => quiet.volume,
+            _ => self.config.volume,
+        }
+    }
+
+    pub fn play(&self) {
+        let volume = self.current_volume();
+        if volume <= 0.0 {
+            return;
+        }
+
+        let sound = self.config.sound.clone();
+
+        thread::spawn(move || {
+            let file = match std::fs::File::open(&sound) {
+                Ok(file) => file,
+                Err(e) => {
+                    eprintln!("Impossible d'ouvrir le carillon {} : {}", sound, e);
+                    return;
+                }
+            };
+
+            let mut stream = match rodio::OutputStreamBuilder::open_default_stream() {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Aucune sortie audio disponible : {}", e);
+                    return;
+                }
+            };
+            stream.log_on_drop(false);
+
+            match rodio::play(stream.mixer(), std::io::BufReader::new(file)) {
+                Ok(sink) => {
+                    sink.set_volume(volume);
+                    sink.sleep_until_end();
+                }
+                Err(e) => eprintln!("Erreur lors de la lecture du carillon : {}", e),
+            }
+        });
+    }
+}
@@ -0,0 +1,160 @@
+use ffmpeg_next::util::color::{Range, Space, TransferCharacteristic};
+use ffmpeg_next::util::frame::video::Video;
+use ffmpeg_next::{self as ffmpeg, ffi};
+use serde::Deserialize;
+
+const SWS_CS_ITU709: i32 = 1;
+const SWS_CS_ITU601: i32 = 5;
+const SWS_CS_BT2020: i32 = 9;
+
+const SDR_WHITE_NITS: f64 = 203.0;
+const HDR_PEAK_NITS: f64 = 1000.0;
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMatrix {
+    #[default]
+    Auto,
+    Bt601,
+    Bt709,
+    Bt2020,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorRange {
+    #[default]
+    Auto,
+    Limited,
+    Full,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(default)]
+pub struct ColorConfig {
+    pub matrix: ColorMatrix,
+    pub range: ColorRange,
+    pub tone_mapping: Option<bool>,
+}
+
+impl ColorConfig {
+    fn matrix(&self, frame: &Video) -> ColorMatrix {
+        if self.matrix != ColorMatrix::Auto {
+            return self.matrix;
+        }
+
+        match frame.color_space() {
+            Space::BT709 => ColorMatrix::Bt709,
+            Space::BT470BG | Space::SMPTE170M => ColorMatrix::Bt601,
+            Space::BT2020NCL | Space::BT2020CL => ColorMatrix::Bt2020,
+            _ if frame.height() >= 720 => ColorMatrix::Bt709,
+            _ => ColorMatrix::Bt601,
+        }
+    }
+
+    fn full_range(&self, frame: &Video) -> bool {
+        match self.range {
+            ColorRange::Full => true,
+            ColorRange::Limited => false,
+            ColorRange::Auto => {
+                frame.color_range() == Range::JPEG
+                    || frame.format() == ffmpeg::format::Pixel::YUVJ420P
+            }
+        }
+    }
+
+    pub fn configure(&self, scaler: &mut ffmpeg::software::scaling::Context, frame: &Video) {
+        let colorspace = match self.matrix(frame) {
+            ColorMatrix::Bt709 => SWS_CS_ITU709,
+            ColorMatrix::Bt2020 => SWS_CS_BT2020,
+            ColorMatrix::Bt601 | ColorMatrix::Auto => SWS_CS_ITU601,
+        };
+        let full_range = self.full_range(frame);
+
+        unsafe {
+            ffi::sws_setColorspaceDetails(
+                scaler.as_mut_ptr(),
+                ffi::sws_getCoefficients(colorspace),
+                i32::from(full_range),
+                ffi::sws_getCoefficients(SWS_CS_ITU601),
+                1,
+                0,
+                1 << 16,
+                1 << 16,
+            );
+        }
+    }
+
+    pub fn tone_mapper(&self, frame: &Video) -> Option<ToneMapper> {
+        let transfer = frame.color_transfer_characteristic();
+        let is_hdr = matches!(
+            transfer,
+            TransferCharacteristic::SMPTE2084 | TransferCharacteristic::ARIB_STD_B67
+        );
+        if !self.tone_mapping.unwrap_or(is_hdr) {
+            return None;
+        }
+
+        let hlg = transfer == TransferCharacteristic::ARIB_STD_B67;
+        Some(ToneMapper::new(hlg))
+    }
+}
+
+pub struct ToneMapper {
+    lut: [u8; 256],
+}
+
+impl ToneMapper {
+    fn new(hlg: bool) -> Self {
+        let white = HDR_PEAK_NITS / SDR_WHITE_NITS;
+        let mut lut = [0; 256];
+
+        for (code, value) in lut.iter_mut().enumerate() {
+            let signal = code as f64 / 255.0;
+            let nits = if hlg {
+                hlg_to_nits(signal)
+            } else {
+                pq_to_nits(signal)
+            };
+
+            let x = nits / SDR_WHITE_NITS;
+            let mapped = x * (1.0 + x / (white * white)) / (1.0 + x);
+            *value = (mapped.clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0).round() as u8;
+        }
+
+        ToneMapper { lut }
+    }
+
+    pub fn apply(&self, rgba: &mut [u8]) {
+        for pixel in rgba.chunks_exact_mut(4) {
+            for channel in &mut pixel[..3] {
+                *channel = self.lut[*channel as usize];
+            }
+        }
+    }
+}
+
+fn pq_to_nits(signal: f64) -> f64 {
+    const M1: f64 = 2610.0 / 16384.0;
+    const M2: f64 = 2523.0 / 4096.0 * 128.0;
+    const C1: f64 = 3424.0 / 4096.0;
+    const C2: f64 = 2413.0 / 4096.0 * 32.0;
+    const C3: f64 = 2392.0 / 4096.0 * 32.0;
+
+    let p = signal.powf(1.0 / M2);
+    let linear = ((p - C1).max(0.0) / (C2 - C3 * p)).powf(1.0 / M1);
+    linear * 10000.0
+}
+
+fn hlg_to_nits(signal: f64) -> f64 {
+    const A: f64 = 0.17883277;
+    const B: f64 = 0.28466892;
+    const C: f64 = 0.55991073;
+
+    let scene = if signal <= 0.5 {
+        signal * signal / 3.0
+    } else {
+        (((signal - C) / A).exp() + B) / 12.0
+    };
+    scene.powf(1.2) * HDR_PEAK_NITS
+}
@@ -0,0 +1,137 @@
+use crate::decoder::{NetworkQuality, StatusUpdate, StreamStatus};
+use crate::frame_store::FrameStore;
+use chrono::{DateTime, Local};
+use eframe::egui::{self, RichText};
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct CameraHealth {
+    status: Option<StreamStatus>,
+    playing: bool,
+    reconnects: u32,
+    last_error: Option<(DateTime<Local>, String)>,
+}
+
+#[derive(Default)]
+pub struct Dashboard {
+    cameras: HashMap<String, CameraHealth>,
+}
+
+impl Dashboard {
+    pub fn record(&mut self, update: &StatusUpdate) {
+        let health = self.cameras.entry(update.url.clone()).or_default();
+
+        match &update.status {
+            StreamStatus::Connecting if health.playing => {
+                health.reconnects += 1;
+                health.playing = false;
+            }
+            StreamStatus::Playing { .. } => health.playing = true,
+            StreamStatus::Error(message) => {
+                health.last_error = Some((Local::now(), message.clone()));
+                health.playing = false;
+            }
+            _ => {}
+        }
+        health.status = Some(update.status.clone());
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui, cameras: &[(String, String)], frame_store: &FrameStore) {
+        let green = egui::Color32::from_rgb(80, 200, 120);
+        let orange = egui::Color32::from_rgb(240, 180, 40);
+        let red = egui::Color32::from_rgb(230, 60, 60);
+
+        ui.add_space(20.0);
+        ui.vertical_centered(|ui| {
+            ui.heading(RichText::new("État des caméras").size(36.).strong());
+        });
+        ui.add_space(20.0);
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("camera_dashboard")
+                .striped(true)
+                .spacing(egui::vec2(32.0, 16.0))
+                .show(ui, |ui| {
+                    for header in [
+                        "Caméra",
+                        "État",
+                        "Dernière image",
+                        "Résolution",
+                        "Codec",
+                        "Débit",
+                        "Reconnexions",
+                        "Dernière erreur",
+                    ] {
+                        ui.label(RichText::new(header).size(22.).strong());
+                    }
+                    ui.end_row();
+
+                    for (name, url) in cameras {
+                        let health = self.cameras.get(url);
+                        let status = health.and_then(|health| health.status.as_ref());
+
+                        ui.label(RichText::new(name).size(22.));
+
+                        let (state, color) = match status {
+                            Some(StreamStatus::Playing { network, .. }) => {
+                                let color = match network.quality() {
+                                    NetworkQuality::Good => green,
+                                    NetworkQuality::Degraded => orange,
+                                    NetworkQuality::Poor => red,
+                                };
+                                ("● Connectée", color)
+                            }
+                            Some(StreamStatus::Connecting) => ("● Connexion...", orange),
+                            Some(StreamStatus::Error(_)) => ("● En erreur", red),
+                            None => ("● Inconnue", egui::Color32::GRAY),
+                        };
+                        ui.label(RichText::new(state).color(color).size(22.));
+
+                        let frame_age = frame_store
+                            .latest(name)
+                            .map(|(time, _)| format_age(time.elapsed().as_secs()))
+                            .unwrap_or_else(|| "—".to_string());
+                        ui.label(RichText::new(frame_age).size(22.));
+
+                        let (resolution, codec, bitrate) = match status {
+                            Some(StreamStatus::Playing {
+                                codec,
+                                decoder,
+                                resolution: (width, height),
+                                network,
+                                ..
+                            }) => (
+                                format!("{}×{}", width, height),
+                                format!("{} ({})", codec, decoder),
+                                format!("{:.1} Mb/s", network.bitrate_kbps as f32 / 1000.0),
+                            ),
+                            _ => ("—".to_string(), "—".to_string(), "—".to_string()),
+                        };
+                        ui.label(RichText::new(resolution).size(22.));
+                        ui.label(RichText::new(codec).size(22.));
+                        ui.label(RichText::new(bitrate).size(22.));
+
+                        let reconnects = health.map_or(0, |health| health.reconnects);
+                        ui.label(RichText::new(reconnects.to_string()).size(22.));
+
+                        let last_error = health
+                            .and_then(|health| health.last_error.as_ref())
+                            .map(|(time, message)| {
+                                format!("{} : {}", time.format("%d/%m %H:%M"), message)
+                            })
+                            .unwrap_or_else(|| "—".to_string());
+                        ui.label(RichText::new(last_error).size(18.));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("il y a {} s", secs),
+        60..3600 => format!("il y a {} min", secs / 60),
+        _ => format!("il y a {} h", secs / 3600),
+    }
+}
@@ -0,0 +1,93 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DayNightConfig {
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub day_start: Option<NaiveTime>,
+    pub night_start: Option<NaiveTime>,
+    #[serde(default = "default_night_dim")]
+    pub night_dim: f32,
+    #[serde(default = "default_sleep_timeout_day_secs")]
+    pub sleep_timeout_day_secs: u64,
+    #[serde(default = "default_sleep_timeout_night_secs")]
+    pub sleep_timeout_night_secs: u64,
+}
+
+fn default_night_dim() -> f32 {
+    0.5
+}
+
+fn default_sleep_timeout_day_secs() -> u64 {
+    60
+}
+
+fn default_sleep_timeout_night_secs() -> u64 {
+    15
+}
+
+fn julian_to_local(julian: f64) -> Option<DateTime<Local>> {
+    let unix = (julian - 2440587.5) * 86400.0;
+    Utc.timestamp_opt(unix as i64, 0)
+        .single()
+        .map(|t| t.with_timezone(&Local))
+}
+
+pub fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> Option<(NaiveTime, NaiveTime)> {
+    let noon = date.and_hms_opt(12, 0, 0)?.and_utc().timestamp() as f64;
+    let julian_day = noon / 86400.0 + 2440587.5;
+    let n = (julian_day - 2451545.0 + 0.0008).ceil();
+
+    let mean_solar_noon = n - longitude / 360.0;
+    let anomaly = (357.5291 + 0.98560028 * mean_solar_noon).rem_euclid(360.0);
+    let m = anomaly.to_radians();
+    let center = 1.9148 * m.sin() + 0.02 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let ecliptic = (anomaly + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = 2451545.0 + mean_solar_noon + 0.0053 * m.sin() - 0.0069 * (2.0 * ecliptic).sin();
+
+    let declination = (ecliptic.sin() * 23.4397_f64.to_radians().sin()).asin();
+    let phi = latitude.to_radians();
+    let cos_hour_angle = ((-0.833_f64).to_radians().sin() - phi.sin() * declination.sin())
+        / (phi.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+    let sunrise = julian_to_local(transit - hour_angle / 360.0)?;
+    let sunset = julian_to_local(transit + hour_angle / 360.0)?;
+    Some((sunrise.time(), sunset.time()))
+}
+
+impl DayNightConfig {
+    fn day_bounds(&self, date: NaiveDate) -> Option<(NaiveTime, NaiveTime)> {
+        if let (Some(latitude), Some(longitude)) = (self.latitude, self.longitude)
+            && let Some(times) = sun_times(date, latitude, longitude)
+        {
+            return Some(times);
+        }
+
+        Some((self.day_start?, self.night_start?))
+    }
+
+    pub fn is_night(&self, now: DateTime<Local>) -> bool {
+        match self.day_bounds(now.date_naive()) {
+            Some((day_start, night_start)) => {
+                let time = now.time();
+                time < day_start || time >= night_start
+            }
+            None => false,
+        }
+    }
+
+    pub fn sleep_timeout_secs(&self, night: bool) -> u64 {
+        if night {
+            self.sleep_timeout_night_secs
+        } else {
+            self.sleep_timeout_day_secs
+        }
+    }
+}
@@ -0,0 +1,910 @@
+use crate::bus::{CameraEvent, EventBus};
+use crate::color::{ColorConfig, ToneMapper};
+use crate::event::{Event, EventKind};
+use crate::net;
+use crate::onvif::MetadataParser;
+use crate::pacing::FrameBudget;
+use crate::restream::Restreamer;
+use crate::{HEIGHT, WIDTH};
+use crossbeam_channel::{Receiver, TryRecvError};
+use eframe::egui;
+use ffmpeg_next::Dictionary;
+use ffmpeg_next::{self as ffmpeg};
+use serde::Deserialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const MAX_HW_PACKETS_WITHOUT_FRAME: u32 = 100;
+const INCIDENT_WINDOW: Duration = Duration::from_secs(300);
+const MAX_INCIDENTS_BEFORE_DOWNGRADE: usize = 3;
+const STALL_THRESHOLD: Duration = Duration::from_secs(3);
+const UPGRADE_AFTER: Duration = Duration::from_secs(600);
+const KEYFRAME_WAIT_ON_WAKE: Duration = Duration::from_secs(2);
+
+pub struct VideoStream {
+    pub url: String,
+    pub bus: EventBus,
+    pub stop_receiver: Receiver<bool>,
+    pub running: bool,
+    pub latency: LatencyMode,
+    pub camera_name: String,
+    pub loud_noise: Option<LoudNoiseConfig>,
+    pub substream_url: Option<String>,
+    pub restream_url: Option<String>,
+    pub ffmpeg_options: BTreeMap<String, String>,
+    pub retry: RetryPolicy,
+    pub looping: bool,
+    pub drop_policy: DropPolicy,
+    pub frame_budget: FrameBudget,
+    pub color: ColorConfig,
+    pub onvif_metadata: bool,
+    pub repaint: Option<egui::Context>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DropPolicy {
+    #[default]
+    DropOldest,
+    SkipToKeyframe,
+    Pause,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub delay_secs: u64,
+    pub max_retries: u32,
+    pub max_delay_secs: u64,
+    pub connect_timeout_secs: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            delay_secs: 5,
+            max_retries: 5,
+            max_delay_secs: 60,
+            connect_timeout_secs: 10,
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self.max_delay_secs.max(self.delay_secs);
+        if attempt >= self.max_retries {
+            return Duration::from_secs(ceiling);
+        }
+
+        let delay = self.delay_secs.saturating_mul(1 << attempt.min(16));
+        Duration::from_secs(delay.min(ceiling))
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LoudNoiseConfig {
+    pub threshold_db: f32,
+    #[serde(default = "default_noise_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+fn default_noise_cooldown_secs() -> u64 {
+    30
+}
+
+struct AudioMonitor {
+    index: usize,
+    decoder: ffmpeg::decoder::Audio,
+    frame: ffmpeg::util::frame::Audio,
+}
+
+impl AudioMonitor {
+    fn open(ictx: &ffmpeg::format::context::Input) -> Option<Self> {
+        let input = ictx.streams().best(ffmpeg::media::Type::Audio)?;
+        let decoder = ffmpeg::codec::context::Context::from_parameters(input.parameters())
+            .and_then(|c| c.decoder().audio())
+            .ok()?;
+
+        Some(AudioMonitor {
+            index: input.index(),
+            decoder,
+            frame: ffmpeg::util::frame::Audio::empty(),
+        })
+    }
+
+    fn peak_level_db(&mut self, packet: &ffmpeg::Packet) -> Option<f32> {
+        self.decoder.send_packet(packet).ok()?;
+
+        let mut peak: Option<f32> = None;
+        while self.decoder.receive_frame(&mut self.frame).is_ok() {
+            if let Some(level) = frame_level_db(&self.frame) {
+                peak = Some(peak.map_or(level, |p| p.max(level)));
+            }
+        }
+        peak
+    }
+}
+
+fn frame_level_db(frame: &ffmpeg::util::frame::Audio) -> Option<f32> {
+    use ffmpeg::format::sample::{Sample, Type};
+
+    let (kind, bytes_per_sample) = match frame.format() {
+        Sample::F32(kind) => (kind, 4),
+        Sample::I16(kind) => (kind, 2),
+        _ => return None,
+    };
+    let count = match kind {
+        Type::Planar => frame.samples(),
+        Type::Packed => frame.samples() * frame.channels() as usize,
+    };
+    if count == 0 {
+        return None;
+    }
+
+    let sum: f64 = frame
+        .data(0)
+        .chunks_exact(bytes_per_sample)
+        .take(count)
+        .map(|bytes| {
+            let value = if bytes_per_sample == 4 {
+                f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+            } else {
+                i16::from_ne_bytes([bytes[0], bytes[1]]) as f64 / 32768.0
+            };
+            value * value
+        })
+        .sum();
+
+    let rms = (sum / count as f64).sqrt().max(1e-9);
+    Some(20.0 * rms.log10() as f32)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NetworkStats {
+    pub bitrate_kbps: u32,
+    pub incidents: u32,
+    pub substream: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkQuality {
+    Good,
+    Degraded,
+    Poor,
+}
+
+impl NetworkStats {
+    pub fn quality(&self) -> NetworkQuality {
+        if self.incidents as usize >= MAX_INCIDENTS_BEFORE_DOWNGRADE {
+            NetworkQuality::Poor
+        } else if self.incidents > 0 || self.substream {
+            NetworkQuality::Degraded
+        } else {
+            NetworkQuality::Good
+        }
+    }
+}
+
+struct BandwidthMonitor {
+    incidents: VecDeque<Instant>,
+    window_start: Instant,
+    window_bytes: usize,
+    bitrate_kbps: u32,
+    last_video_packet: Instant,
+    substream_since: Option<Instant>,
+}
+
+impl BandwidthMonitor {
+    fn new() -> Self {
+        BandwidthMonitor {
+            incidents: VecDeque::new(),
+            window_start: Instant::now(),
+            window_bytes: 0,
+            bitrate_kbps: 0,
+            last_video_packet: Instant::now(),
+            substream_since: None,
+        }
+    }
+
+    fn reset_session(&mut self) {
+        self.window_start = Instant::now();
+        self.window_bytes = 0;
+        self.bitrate_kbps = 0;
+        self.last_video_packet = Instant::now();
+    }
+
+    fn record_incident(&mut self) {
+        self.incidents.push_back(Instant::now());
+    }
+
+    fn recent_incidents(&mut self) -> usize {
+        while self
+            .incidents
+            .front()
+            .is_some_and(|t| t.elapsed() > INCIDENT_WINDOW)
+        {
+            self.incidents.pop_front();
+        }
+        self.incidents.len()
+    }
+
+    fn record_packet(&mut self, size: usize) -> bool {
+        self.window_bytes += size;
+
+        let elapsed = self.window_start.elapsed();
+        if elapsed < Duration::from_secs(1) {
+            return false;
+        }
+
+        self.bitrate_kbps =
+            (self.window_bytes as f64 * 8.0 / 1000.0 / elapsed.as_secs_f64()) as u32;
+        self.window_bytes = 0;
+        self.window_start = Instant::now();
+        true
+    }
+
+    fn record_video_packet(&mut self) {
+        if self.last_video_packet.elapsed() > STALL_THRESHOLD {
+            self.record_incident();
+        }
+        self.last_video_packet = Instant::now();
+    }
+
+    fn wants_substream(&mut self) -> bool {
+        let incidents = self.recent_incidents();
+
+        match self.substream_since {
+            Some(since) if since.elapsed() >= UPGRADE_AFTER && incidents == 0 => {
+                self.substream_since = None;
+                false
+            }
+            Some(_) => true,
+            None if incidents >= MAX_INCIDENTS_BEFORE_DOWNGRADE => {
+                self.substream_since = Some(Instant::now());
+                self.incidents.clear();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn stats(&mut self, substream: bool) -> NetworkStats {
+        NetworkStats {
+            bitrate_kbps: self.bitrate_kbps,
+            incidents: self.recent_incidents() as u32,
+            substream,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LatencyMode {
+    Low,
+    #[default]
+    Normal,
+    Smooth,
+}
+
+pub struct VideoFrame {
+    pub data: Vec<u8>,
+    pub url: String,
+    pub camera: String,
+    pub pts: Option<Duration>,
+    pub received_at: Instant,
+    pub decoded_at: Instant,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamStatus {
+    Connecting,
+    Playing {
+        codec: String,
+        decoder: String,
+        resolution: (u32, u32),
+        fps: f64,
+        network: NetworkStats,
+    },
+    Error(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct StatusUpdate {
+    pub url: String,
+    pub status: StreamStatus,
+}
+
+impl VideoStream {
+    fn poll_commands(&self, running: &mut bool, woke_at: &mut Option<Instant>) -> bool {
+        loop {
+            match self.stop_receiver.try_recv() {
+                Ok(value) => {
+                    if value && !*running {
+                        *woke_at = Some(Instant::now());
+                    }
+                    *running = value;
+                }
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    fn report(&self, status: StreamStatus) {
+        self.bus.publish(CameraEvent::StreamStatus(StatusUpdate {
+            url: self.url.clone(),
+            status,
+        }));
+    }
+}
+
+fn hw_decoder_names(codec_id: ffmpeg::codec::Id) -> &'static [&'static str] {
+    match codec_id {
+        ffmpeg::codec::Id::H264 => &["h264_v4l2m2m", "h264_rkmpp"],
+        ffmpeg::codec::Id::HEVC => &["hevc_v4l2m2m", "hevc_rkmpp"],
+        ffmpeg::codec::Id::VP8 => &["vp8_v4l2m2m"],
+        ffmpeg::codec::Id::VP9 => &["vp9_v4l2m2m", "vp9_rkmpp"],
+        _ => &[],
+    }
+}
+
+fn input_options(
+    use_tcp_for_rtsp: bool,
+    latency: LatencyMode,
+    fast_start: bool,
+    connect_timeout: Duration,
+    extra: &BTreeMap<String, String>,
+) -> Dictionary<'static> {
+    let mut opts = Dictionary::new();
+    opts.set("timeout", &connect_timeout.as_micros().to_string());
+    if use_tcp_for_rtsp {
+        opts.set("rtsp_transport", "tcp");
+    }
+
+    match latency {
+        LatencyMode::Low => {
+            opts.set("probesize", "32768");
+            opts.set("analyzeduration", "500000");
+            opts.set("fflags", "nobuffer");
+            opts.set("flags", "low_delay");
+            opts.set("reorder_queue_size", "0");
+            opts.set("max_delay", "0");
+        }
+        LatencyMode::Normal => {}
+        LatencyMode::Smooth => {
+            opts.set("reorder_queue_size", "500");
+            opts.set("max_delay", "1000000");
+            opts.set("buffer_size", "4194304");
+        }
+    }
+
+    if fast_start {
+        opts.set("probesize", "32768");
+        opts.set("analyzeduration", "100000");
+    }
+
+    net::apply_bind_address(&mut opts);
+    for (key, value) in extra {
+        opts.set(key, value);
+    }
+
+    opts
+}
+
+fn codec_context(
+    params: ffmpeg::codec::Parameters,
+    latency: LatencyMode,
+) -> Result<ffmpeg::codec::context::Context, ffmpeg::Error> {
+    let mut context = ffmpeg::codec::context::Context::from_parameters(params)?;
+    if latency == LatencyMode::Low {
+        context.set_flags(ffmpeg::codec::Flags::LOW_DELAY);
+        context.set_threading(ffmpeg::codec::threading::Config::kind(
+            ffmpeg::codec::threading::Type::Slice,
+        ));
+    }
+    Ok(context)
+}
+
+fn open_decoder(
+    params: ffmpeg::codec::Parameters,
+    allow_hw: bool,
+    latency: LatencyMode,
+) -> Result<(ffmpeg::decoder::Video, String), String> {
+    let codec_id = params.id();
+
+    if allow_hw {
+        for name in hw_decoder_names(codec_id) {
+            let Some(hw_codec) = ffmpeg::decoder::find_by_name(name) else {
+                continue;
+            };
+
+            match codec_context(params.clone(), latency)
+                .and_then(|c| c.decoder().open_as(hw_codec))
+                .and_then(|c| c.video())
+            {
+                Ok(hw_dec) => {
+                    println!("Accélération matérielle activée : {}", name);
+                    return Ok((hw_dec, name.to_string()));
+                }
+                Err(_) => println!("Échec matériel pour {}, essai suivant...", name),
+            }
+        }
+    }
+
+    let Some(codec) = ffmpeg::decoder::find(codec_id) else {
+        return Err(format!("Codec non pris en charge : {}", codec_id.name()));
+    };
+
+    println!("Décodage logiciel avec {}", codec.name());
+    let name = codec.name().to_string();
+    codec_context(params, latency)
+        .and_then(|c| c.decoder().open_as(codec))
+        .and_then(|c| c.video())
+        .map(|decoder| (decoder, name))
+        .map_err(|e| {
+            format!(
+                "Impossible d'ouvrir le décodeur {} : {}",
+                codec_id.name(),
+                e
+            )
+        })
+}
+
+pub fn run_decoder_managed(
+    video_stream: VideoStream,
+    has_to_wait_for_keyframe: bool,
+    use_tcp_for_rtsp: bool,
+) {
+    let mut running = video_stream.running;
+    let mut waiting_for_keyframe = true;
+    let mut allow_hw = true;
+    let mut last_noise_event: Option<Instant> = None;
+    let mut bandwidth = BandwidthMonitor::new();
+    let mut failed_attempts = 0;
+    let mut woke_at: Option<Instant> = None;
+    let mut fast_start = false;
+
+    while video_stream.poll_commands(&mut running, &mut woke_at) {
+        video_stream.report(StreamStatus::Connecting);
+
+        let use_substream = video_stream.substream_url.is_some() && bandwidth.wants_substream();
+        let url = match (&video_stream.substream_url, use_substream) {
+            (Some(substream_url), true) => substream_url,
+            _ => &video_stream.url,
+        };
+
+        let opts = input_options(
+            use_tcp_for_rtsp,
+            video_stream.latency,
+            std::mem::take(&mut fast_start),
+            Duration::from_secs(video_stream.retry.connect_timeout_secs),
+            &video_stream.ffmpeg_options,
+        );
+
+        let mut ictx = match ffmpeg::format::input_with_dictionary(&net::resolve_url(url), opts) {
+            Ok(ctx) => ctx,
+            Err(e) => {
+                bandwidth.record_incident();
+                video_stream.report(StreamStatus::Error(format!("Connexion impossible : {}", e)));
+                std::thread::sleep(video_stream.retry.delay(failed_attempts));
+                failed_attempts += 1;
+                continue;
+            }
+        };
+
+        let Some(input) = ictx.streams().best(ffmpeg::media::Type::Video) else {
+            video_stream.report(StreamStatus::Error("Aucun flux vidéo".to_string()));
+            std::thread::sleep(Duration::from_secs(30));
+            continue;
+        };
+        let video_index = input.index();
+        let time_base = input.time_base();
+        let fps = f64::from(input.avg_frame_rate());
+
+        let mut restreamer = video_stream
+            .restream_url
+            .as_deref()
+            .and_then(|restream_url| {
+                Restreamer::open(restream_url, &input)
+                    .map_err(|e| eprintln!("Rediffusion impossible vers {} : {}", restream_url, e))
+                    .ok()
+            });
+
+        let params = input.parameters();
+        let codec_id = params.id();
+        let codec_name = codec_id.name().to_string();
+
+        let (mut decoder, decoder_name) = match open_decoder(params, allow_hw, video_stream.latency)
+        {
+            Ok(decoder) => decoder,
+            Err(message) => {
+                eprintln!("{} ({})", message, url);
+                video_stream.report(StreamStatus::Error(message));
+                std::thread::sleep(Duration::from_secs(30));
+                continue;
+            }
+        };
+        let using_hw = hw_decoder_names(codec_id).contains(&decoder_name.as_str());
+        let resolution = (decoder.width(), decoder.height());
+
+        if use_substream {
+            println!("Flux secondaire utilisé pour {}", video_stream.camera_name);
+        }
+        bandwidth.reset_session();
+        failed_attempts = 0;
+        video_stream.report(StreamStatus::Playing {
+            codec: codec_name.clone(),
+            decoder: decoder_name.clone(),
+            resolution,
+            fps,
+            network: bandwidth.stats(use_substream),
+        });
+
+        let mut audio_monitor = video_stream
+            .loud_noise
+            .as_ref()
+            .and_then(|_| AudioMonitor::open(&ictx));
+
+        let metadata_index = ictx
+            .streams()
+            .find(|stream| stream.parameters().medium() == ffmpeg::media::Type::Data)
+            .map(|stream| stream.index())
+            .filter(|_| video_stream.onvif_metadata);
+        let mut metadata_parser = MetadataParser::default();
+
+        let mut scaler: Option<ffmpeg::software::scaling::context::Context> = None;
+        let mut tone_mapper: Option<ToneMapper> = None;
+        let mut frame = ffmpeg::util::frame::video::Video::empty();
+        let mut frame_rgba = ffmpeg::util::frame::video::Video::empty();
+        let mut packets_without_frame = 0;
+        let mut connection_lost = true;
+        let mut replay_clock: Option<(Duration, Instant)> = None;
+        let mut skipping_to_keyframe = false;
+
+        woke_at = None;
+
+        for (stream, packet) in ictx.packets() {
+            if !video_stream.poll_commands(&mut running, &mut woke_at) {
+                return;
+            }
+
+            if bandwidth.record_packet(packet.size()) {
+                video_stream.report(StreamStatus::Playing {
+                    codec: codec_name.clone(),
+                    decoder: decoder_name.clone(),
+                    resolution,
+                    fps,
+                    network: bandwidth.stats(use_substream),
+                });
+            }
+
+            if let (Some(audio), Some(config)) = (audio_monitor.as_mut(), &video_stream.loud_noise)
+                && stream.index() == audio.index
+            {
+                let cooled_down = last_noise_event
+                    .is_none_or(|last| last.elapsed().as_secs() >= config.cooldown_secs);
+
+                if let Some(level) = audio.peak_level_db(&packet)
+                    && level >= config.threshold_db
+                    && cooled_down
+                {
+                    last_noise_event = Some(Instant::now());
+                    video_stream.bus.publish(CameraEvent::Detected(Event {
+                        camera: video_stream.camera_name.clone(),
+                        kind: EventKind::LoudNoise,
+                        details: None,
+                    }));
+                }
+                continue;
+            }
+
+            if Some(stream.index()) == metadata_index {
+                if let Some(objects) = packet.data().and_then(|data| metadata_parser.push(data)) {
+                    video_stream.bus.publish(CameraEvent::Analytics {
+                        url: video_stream.url.clone(),
+                        objects,
+                    });
+                }
+                continue;
+            }
+
+            if stream.index() != video_index {
+                continue;
+            }
+
+            bandwidth.record_video_packet();
+            let received_at = Instant::now();
+
+            if video_stream.looping
+                && let Some(pts) = packet
+                    .pts()
+                    .filter(|pts| *pts >= 0 && time_base.denominator() != 0)
+            {
+                let position = Duration::from_secs_f64(pts as f64 * f64::from(time_base));
+                let (start_pts, start) = *replay_clock.get_or_insert((position, Instant::now()));
+                let due = start + position.saturating_sub(start_pts);
+                if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                    std::thread::sleep(wait);
+                }
+            }
+
+            if let Some(output) = restreamer.as_mut()
+                && let Err(e) = output.write(&packet)
+            {
+                eprintln!(
+                    "Rediffusion interrompue pour {} : {}",
+                    video_stream.camera_name, e
+                );
+                restreamer = None;
+            }
+            if video_stream.substream_url.is_some() && bandwidth.wants_substream() != use_substream
+            {
+                connection_lost = false;
+                break;
+            }
+
+            if !running {
+                continue;
+            }
+
+            if let Some(woke) = woke_at {
+                if packet.is_key() {
+                    decoder.flush();
+                    woke_at = None;
+                    waiting_for_keyframe = false;
+                } else if woke.elapsed() > KEYFRAME_WAIT_ON_WAKE {
+                    println!(
+                        "Pas d'image clé après le réveil de {}, reconnexion rapide...",
+                        video_stream.camera_name
+                    );
+                    fast_start = true;
+                    connection_lost = false;
+                    break;
+                } else {
+                    continue;
+                }
+            }
+
+            if video_stream.frame_budget.is_saturated() {
+                match video_stream.drop_policy {
+                    DropPolicy::DropOldest => {}
+                    DropPolicy::SkipToKeyframe => skipping_to_keyframe = true,
+                    DropPolicy::Pause => {
+                        while video_stream.frame_budget.is_saturated() && running {
+                            if !video_stream.poll_commands(&mut running, &mut woke_at) {
+                                return;
+                            }
+                            std::thread::sleep(Duration::from_millis(10));
+                        }
+                    }
+                }
+            }
+
+            if skipping_to_keyframe {
+                if packet.is_key() {
+                    decoder.flush();
+                    skipping_to_keyframe = false;
+                } else {
+                    video_stream.frame_budget.record_dropped(1);
+                    continue;
+                }
+            }
+
+            if has_to_wait_for_keyframe && waiting_for_keyframe {
+                if !packet.is_key() {
+                    continue;
+                } else {
+                    waiting_for_keyframe = false;
+                }
+            }
+
+            let _ = decoder.send_packet(&packet);
+            packets_without_frame += 1;
+
+            while decoder.receive_frame(&mut frame).is_ok() {
+                packets_without_frame = 0;
+
+                let needs_scaler = scaler.as_ref().is_none_or(|s| {
+                    let input = s.input();
+                    input.format != frame.format()
+                        || input.width != frame.width()
+                        || input.height != frame.height()
+                });
+                if needs_scaler {
+                    scaler = ffmpeg::software::scaling::context::Context::get(
+                        frame.format(),
+                        frame.width(),
+                        frame.height(),
+                        ffmpeg::format::Pixel::RGBA,
+                        WIDTH,
+                        HEIGHT,
+                        ffmpeg::software::scaling::flag::Flags::BILINEAR,
+                    )
+                    .ok();
+                    if let Some(scaler) = scaler.as_mut() {
+                        video_stream.color.configure(scaler, &frame);
+                    }
+                    tone_mapper = video_stream.color.tone_mapper(&frame);
+                }
+
+                let Some(scaler) = scaler.as_mut() else {
+                    continue;
+                };
+                if scaler.run(&frame, &mut frame_rgba).is_err() {
+                    continue;
+                }
+
+                let pts = frame
+                    .timestamp()
+                    .filter(|pts| *pts >= 0 && time_base.denominator() != 0)
+                    .map(|pts| Duration::from_secs_f64(pts as f64 * f64::from(time_base)));
+
+                let mut data = frame_rgba.data(0).to_vec();
+                if let Some(tone_mapper) = &tone_mapper {
+                    tone_mapper.apply(&mut data);
+                }
+
+                video_stream.frame_budget.reserve();
+                video_stream
+                    .bus
+                    .publish(CameraEvent::FrameReady(Arc::new(VideoFrame {
+                        data,
+                        url: video_stream.url.clone(),
+                        camera: video_stream.camera_name.clone(),
+                        pts,
+                        received_at,
+                        decoded_at: Instant::now(),
+                    })));
+                if let Some(repaint) = &video_stream.repaint {
+                    repaint.request_repaint();
+                }
+            }
+
+            if using_hw && packets_without_frame > MAX_HW_PACKETS_WITHOUT_FRAME {
+                println!("Le décodeur matériel échoue, repli logiciel...");
+                allow_hw = false;
+                connection_lost = false;
+                break;
+            }
+        }
+
+        if connection_lost && !video_stream.looping {
+            bandwidth.record_incident();
+        }
+        waiting_for_keyframe = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, write_fixture};
+    use std::thread;
+
+    #[test]
+    fn retry_policy_backs_off_up_to_the_ceiling() {
+        let policy = RetryPolicy {
+            delay_secs: 2,
+            max_retries: 3,
+            max_delay_secs: 10,
+            connect_timeout_secs: 10,
+        };
+
+        assert_eq!(policy.delay(0), Duration::from_secs(2));
+        assert_eq!(policy.delay(1), Duration::from_secs(4));
+        assert_eq!(policy.delay(2), Duration::from_secs(8));
+        assert_eq!(policy.delay(3), Duration::from_secs(10));
+        assert_eq!(policy.delay(40), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn frames_flow_from_a_file_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("source.y4m");
+        write_fixture(&path, 10);
+
+        let bus = EventBus::default();
+        let events = bus.subscribe();
+        let (stream, _stop) = testing::video_stream(path.to_str().unwrap(), &bus);
+        thread::spawn(move || run_decoder_managed(stream, false, false));
+
+        let frame = testing::wait_for(&events, Duration::from_secs(10), |event| match event {
+            CameraEvent::FrameReady(frame) => Some(frame),
+            _ => None,
+        })
+        .expect("aucune image décodée");
+
+        assert_eq!(frame.camera, "Test");
+        assert_eq!(frame.data.len(), (WIDTH * HEIGHT * 4) as usize);
+    }
+
+    #[test]
+    fn reconnects_when_the_source_ends() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("source.y4m");
+        write_fixture(&path, 2);
+
+        let bus = EventBus::default();
+        let events = bus.subscribe();
+        let (stream, _stop) = testing::video_stream(path.to_str().unwrap(), &bus);
+        thread::spawn(move || run_decoder_managed(stream, false, false));
+
+        let mut sessions = 0;
+        let reconnected = testing::wait_for(&events, Duration::from_secs(10), |event| {
+            if let CameraEvent::StreamStatus(update) = event
+                && matches!(update.status, StreamStatus::Playing { .. })
+            {
+                sessions += 1;
+            }
+            (sessions >= 2).then_some(())
+        });
+
+        assert!(reconnected.is_some());
+    }
+
+    #[test]
+    fn replays_a_looping_file_at_playback_speed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("source.y4m");
+        write_fixture(&path, 5);
+
+        let bus = EventBus::default();
+        let events = bus.subscribe();
+        let (mut stream, _stop) = testing::video_stream(path.to_str().unwrap(), &bus);
+        stream.looping = true;
+        let start = Instant::now();
+        thread::spawn(move || run_decoder_managed(stream, false, false));
+
+        let mut sessions = 0;
+        let replayed = testing::wait_for(&events, Duration::from_secs(10), |event| {
+            if let CameraEvent::StreamStatus(update) = event
+                && matches!(update.status, StreamStatus::Playing { .. })
+            {
+                sessions += 1;
+            }
+            (sessions >= 2).then_some(())
+        });
+
+        assert!(replayed.is_some());
+        assert!(start.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[test]
+    fn retries_an_unreachable_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.y4m");
+
+        let bus = EventBus::default();
+        let events = bus.subscribe();
+        let (stream, _stop) = testing::video_stream(path.to_str().unwrap(), &bus);
+        thread::spawn(move || run_decoder_managed(stream, false, false));
+
+        let mut errors = 0;
+        let retried = testing::wait_for(&events, Duration::from_secs(10), |event| {
+            if let CameraEvent::StreamStatus(update) = event
+                && matches!(update.status, StreamStatus::Error(_))
+            {
+                errors += 1;
+            }
+            (errors >= 2).then_some(())
+        });
+
+        assert!(retried.is_some());
+    }
+
+    #[test]
+    fn stops_when_the_controller_goes_away() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("source.y4m");
+        write_fixture(&path, 2);
+
+        let bus = EventBus::default();
+        let (stream, stop) = testing::video_stream(path.to_str().unwrap(), &bus);
+        let handle = thread::spawn(move || run_decoder_managed(stream, false, false));
+        drop(stop);
+
+        handle.join().unwrap();
+    }
+}
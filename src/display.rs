@@ -0,0 +1,394 @@
+use crate::onvif::AnalyticsObject;
+use eframe::egui;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayMode {
+    #[default]
+    Fit,
+    Fill,
+    Stretch,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlsPosition {
+    #[default]
+    Bottom,
+    Top,
+    Left,
+    Right,
+    Floating,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(try_from = "u16")]
+pub enum Rotation {
+    #[default]
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+impl TryFrom<u16> for Rotation {
+    type Error = String;
+
+    fn try_from(degrees: u16) -> Result<Self, Self::Error> {
+        match degrees {
+            0 => Ok(Rotation::None),
+            90 => Ok(Rotation::Quarter),
+            180 => Ok(Rotation::Half),
+            270 => Ok(Rotation::ThreeQuarters),
+            _ => Err(format!(
+                "rotation invalide : {} (0, 90, 180 ou 270)",
+                degrees
+            )),
+        }
+    }
+}
+
+impl Rotation {
+    fn quarter_turns(self) -> u8 {
+        match self {
+            Rotation::None => 0,
+            Rotation::Quarter => 1,
+            Rotation::Half => 2,
+            Rotation::ThreeQuarters => 3,
+        }
+    }
+
+    pub fn angle(self) -> f32 {
+        f32::from(self.quarter_turns()) * std::f32::consts::FRAC_PI_2
+    }
+
+    fn rotate(self, vec: egui::Vec2) -> egui::Vec2 {
+        (0..self.quarter_turns()).fold(vec, |vec, _| egui::vec2(-vec.y, vec.x))
+    }
+
+    fn view_size(self, size: egui::Vec2) -> egui::Vec2 {
+        match self {
+            Rotation::Quarter | Rotation::ThreeQuarters => egui::vec2(size.y, size.x),
+            _ => size,
+        }
+    }
+
+    fn to_image(self, view: egui::Pos2) -> egui::Pos2 {
+        match self {
+            Rotation::None => view,
+            Rotation::Quarter => egui::pos2(view.y, 1.0 - view.x),
+            Rotation::Half => egui::pos2(1.0 - view.x, 1.0 - view.y),
+            Rotation::ThreeQuarters => egui::pos2(1.0 - view.y, view.x),
+        }
+    }
+
+    fn to_view(self, image: egui::Pos2) -> egui::Pos2 {
+        match self {
+            Rotation::None => image,
+            Rotation::Quarter => egui::pos2(1.0 - image.y, image.x),
+            Rotation::Half => egui::pos2(1.0 - image.x, 1.0 - image.y),
+            Rotation::ThreeQuarters => egui::pos2(image.y, 1.0 - image.x),
+        }
+    }
+}
+
+pub fn paint_icon(
+    painter: &egui::Painter,
+    center: egui::Pos2,
+    icon: &str,
+    size: f32,
+    rotation: Rotation,
+) {
+    let galley = painter.layout_no_wrap(
+        icon.to_string(),
+        egui::FontId::proportional(size),
+        egui::Color32::WHITE,
+    );
+    let angle = rotation.angle();
+    let offset = egui::emath::Rot2::from_angle(angle) * (-galley.size() / 2.0);
+    painter.add(
+        egui::epaint::TextShape::new(center + offset, galley, egui::Color32::WHITE)
+            .with_angle(angle),
+    );
+}
+
+impl ControlsPosition {
+    pub fn on_screen(self, rotation: Rotation) -> Self {
+        let order = [
+            ControlsPosition::Bottom,
+            ControlsPosition::Left,
+            ControlsPosition::Top,
+            ControlsPosition::Right,
+        ];
+        match order.iter().position(|position| *position == self) {
+            Some(index) => order[(index + usize::from(rotation.quarter_turns())) % order.len()],
+            None => self,
+        }
+    }
+
+    pub fn anchor(self) -> Option<(egui::Align2, egui::Vec2)> {
+        match self {
+            ControlsPosition::Bottom => Some((egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -10.0))),
+            ControlsPosition::Top => Some((egui::Align2::CENTER_TOP, egui::vec2(0.0, 10.0))),
+            ControlsPosition::Left => Some((egui::Align2::LEFT_CENTER, egui::vec2(10.0, 0.0))),
+            ControlsPosition::Right => Some((egui::Align2::RIGHT_CENTER, egui::vec2(-10.0, 0.0))),
+            ControlsPosition::Floating => None,
+        }
+    }
+
+    pub fn clearance(self) -> (f32, f32) {
+        match self {
+            ControlsPosition::Bottom => (10.0, 160.0),
+            ControlsPosition::Top => (160.0, 10.0),
+            _ => (10.0, 10.0),
+        }
+    }
+
+    pub fn layout(self, rotation: Rotation) -> egui::Layout {
+        let along = match self {
+            ControlsPosition::Left | ControlsPosition::Right => egui::vec2(0.0, 1.0),
+            _ => egui::vec2(1.0, 0.0),
+        };
+        let direction = rotation.rotate(along);
+        if direction.x > 0.5 {
+            egui::Layout::left_to_right(egui::Align::Center)
+        } else if direction.x < -0.5 {
+            egui::Layout::right_to_left(egui::Align::Center)
+        } else if direction.y > 0.5 {
+            egui::Layout::top_down(egui::Align::Center)
+        } else {
+            egui::Layout::bottom_up(egui::Align::Center)
+        }
+    }
+}
+
+pub fn is_portrait(ctx: &egui::Context) -> bool {
+    let screen = ctx.viewport_rect();
+    screen.height() > screen.width()
+}
+
+pub fn fit_rect(available: egui::Rect, image_size: egui::Vec2) -> egui::Rect {
+    let scale = (available.width() / image_size.x).min(available.height() / image_size.y);
+    egui::Rect::from_center_size(available.center(), image_size * scale)
+}
+
+const MAX_ZOOM: f32 = 8.0;
+const SCROLL_ZOOM_SPEED: f32 = 200.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zoom {
+    pub scale: f32,
+    pub center: egui::Pos2,
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Zoom {
+            scale: 1.0,
+            center: egui::pos2(0.5, 0.5),
+        }
+    }
+}
+
+impl Zoom {
+    fn uv(&self, base: egui::Rect) -> egui::Rect {
+        egui::Rect::from_center_size(self.center, base.size() / self.scale)
+    }
+
+    fn clamp(&mut self, base: egui::Rect) {
+        let half = base.size() / self.scale / 2.0;
+        self.center = egui::pos2(
+            self.center
+                .x
+                .max(base.min.x + half.x)
+                .min(base.max.x - half.x),
+            self.center
+                .y
+                .max(base.min.y + half.y)
+                .min(base.max.y - half.y),
+        );
+    }
+}
+
+fn layout(
+    available: egui::Rect,
+    image_size: egui::Vec2,
+    mode: DisplayMode,
+) -> (egui::Rect, egui::Rect) {
+    let full = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+
+    match mode {
+        DisplayMode::Fit => (fit_rect(available, image_size), full),
+        DisplayMode::Stretch => (available, full),
+        DisplayMode::Fill => {
+            let scale = (available.width() / image_size.x).max(available.height() / image_size.y);
+            let visible = available.size() / (image_size * scale);
+            let uv = egui::Rect::from_center_size(egui::pos2(0.5, 0.5), visible);
+            (available, uv)
+        }
+    }
+}
+
+fn paint_rotated(
+    painter: &egui::Painter,
+    texture: egui::TextureId,
+    rect: egui::Rect,
+    uv: egui::Rect,
+    rotation: Rotation,
+) {
+    if rotation == Rotation::None {
+        painter.image(texture, rect, uv, egui::Color32::WHITE);
+        return;
+    }
+
+    let mut mesh = egui::Mesh::with_texture(texture);
+    let corners = [
+        (rect.left_top(), uv.left_top()),
+        (rect.right_top(), uv.right_top()),
+        (rect.right_bottom(), uv.right_bottom()),
+        (rect.left_bottom(), uv.left_bottom()),
+    ];
+    for (pos, view) in corners {
+        mesh.vertices.push(egui::epaint::Vertex {
+            pos,
+            uv: rotation.to_image(view),
+            color: egui::Color32::WHITE,
+        });
+    }
+    mesh.add_triangle(0, 1, 2);
+    mesh.add_triangle(0, 2, 3);
+    painter.add(mesh);
+}
+
+pub fn show_image(
+    ui: &mut egui::Ui,
+    texture: &egui::TextureHandle,
+    mode: DisplayMode,
+    rotation: Rotation,
+) -> (egui::Rect, egui::Rect) {
+    let available = ui.available_rect_before_wrap();
+    let (rect, uv) = layout(available, rotation.view_size(texture.size_vec2()), mode);
+
+    paint_rotated(ui.painter(), texture.id(), rect, uv, rotation);
+    ui.allocate_rect(available, egui::Sense::hover());
+    (rect, uv)
+}
+
+pub fn show_zoomable_image(
+    ui: &mut egui::Ui,
+    texture: &egui::TextureHandle,
+    mode: DisplayMode,
+    rotation: Rotation,
+    zoom: &mut Zoom,
+) -> (egui::Rect, egui::Rect) {
+    let available = ui.available_rect_before_wrap();
+    let (rect, base) = layout(available, rotation.view_size(texture.size_vec2()), mode);
+    let response = ui.allocate_rect(available, egui::Sense::click_and_drag());
+
+    if response.hovered() {
+        let (scroll, pinch, pointer) = ui.input(|i| {
+            (
+                i.smooth_scroll_delta.y,
+                i.zoom_delta(),
+                i.pointer.hover_pos(),
+            )
+        });
+        let factor = pinch * (scroll / SCROLL_ZOOM_SPEED).exp();
+        if factor != 1.0 {
+            let before = zoom.uv(base);
+            let anchor = pointer
+                .filter(|pointer| rect.contains(*pointer))
+                .unwrap_or(rect.center());
+            let offset = (anchor - rect.min) / rect.size();
+            let target = before.min + offset * before.size();
+
+            zoom.scale = (zoom.scale * factor).clamp(1.0, MAX_ZOOM);
+            let size = base.size() / zoom.scale;
+            zoom.center = target + (egui::vec2(0.5, 0.5) - offset) * size;
+        }
+    }
+    if response.dragged() {
+        let uv = zoom.uv(base);
+        zoom.center -= response.drag_delta() / rect.size() * uv.size();
+    }
+    if response.double_clicked() {
+        *zoom = Zoom::default();
+    }
+    zoom.clamp(base);
+
+    let uv = zoom.uv(base);
+    paint_rotated(ui.painter(), texture.id(), rect, uv, rotation);
+    (rect, uv)
+}
+
+pub fn draw_objects(
+    ui: &egui::Ui,
+    rect: egui::Rect,
+    uv: egui::Rect,
+    rotation: Rotation,
+    objects: &[AnalyticsObject],
+) {
+    let to_screen = |x: f32, y: f32| {
+        let view = rotation.to_view(egui::pos2(x, y));
+        egui::pos2(
+            rect.min.x + (view.x - uv.min.x) / uv.width() * rect.width(),
+            rect.min.y + (view.y - uv.min.y) / uv.height() * rect.height(),
+        )
+    };
+    let color = egui::Color32::from_rgb(80, 200, 120);
+    let painter = ui.painter_at(rect);
+
+    for object in objects {
+        let bounds = egui::Rect::from_two_pos(
+            to_screen(object.left, object.top),
+            to_screen(object.right, object.bottom),
+        );
+        painter.rect_stroke(
+            bounds,
+            0.0,
+            egui::Stroke::new(3.0, color),
+            egui::StrokeKind::Outside,
+        );
+        if let Some(class) = &object.class {
+            painter.text(
+                bounds.left_top() - egui::vec2(0.0, 4.0),
+                egui::Align2::LEFT_BOTTOM,
+                class,
+                egui::FontId::proportional(20.0),
+                color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_maps_view_and_image_coordinates_back_and_forth() {
+        for rotation in [
+            Rotation::None,
+            Rotation::Quarter,
+            Rotation::Half,
+            Rotation::ThreeQuarters,
+        ] {
+            let point = egui::pos2(0.2, 0.7);
+            let back = rotation.to_image(rotation.to_view(point));
+            assert!((back - point).length() < 1e-6, "{:?}", rotation);
+        }
+        assert_eq!(
+            Rotation::Quarter.to_view(egui::pos2(0.0, 0.0)),
+            egui::pos2(1.0, 0.0)
+        );
+        assert_eq!(
+            ControlsPosition::Bottom.on_screen(Rotation::Quarter),
+            ControlsPosition::Left
+        );
+        assert_eq!(
+            Rotation::try_from(45),
+            Err("rotation invalide : 45 (0, 90, 180 ou 270)".to_string())
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use crate::chime::QuietHours;
+use chrono::{NaiveDateTime, NaiveTime, TimeDelta};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DndConfig {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+#[derive(Default)]
+pub struct DoNotDisturb {
+    schedule: Option<QuietHours>,
+    forced: Option<(bool, Option<NaiveDateTime>)>,
+}
+
+impl DoNotDisturb {
+    pub fn new(config: Option<&DndConfig>) -> Self {
+        DoNotDisturb {
+            schedule: config.map(|config| QuietHours {
+                start: config.start,
+                end: config.end,
+                volume: 0.0,
+            }),
+            forced: None,
+        }
+    }
+
+    fn scheduled(&self, time: NaiveTime) -> bool {
+        self.schedule
+            .as_ref()
+            .is_some_and(|schedule| schedule.contains(time))
+    }
+
+    fn next_boundary(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let schedule = self.schedule.as_ref()?;
+        [schedule.start, schedule.end]
+            .into_iter()
+            .map(|time| {
+                let boundary = now.date().and_time(time);
+                if boundary > now {
+                    boundary
+                } else {
+                    boundary + TimeDelta::days(1)
+                }
+            })
+            .min()
+    }
+
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        if let Some((active, until)) = self.forced
+            && until.is_none_or(|until| now < until)
+        {
+            return active;
+        }
+        self.scheduled(now.time())
+    }
+
+    pub fn set(&mut self, now: NaiveDateTime, active: bool) {
+        self.forced = Some((active, self.next_boundary(now)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_toggle_lasts_until_schedule_changes() {
+        let time = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        let at = |day, hour| {
+            chrono::NaiveDate::from_ymd_opt(2025, 3, day)
+                .unwrap()
+                .and_time(time(hour))
+        };
+        let mut dnd = DoNotDisturb::new(Some(&DndConfig {
+            start: time(22),
+            end: time(7),
+        }));
+        assert!(dnd.is_active(at(1, 23)));
+        assert!(!dnd.is_active(at(1, 12)));
+
+        dnd.set(at(1, 23), false);
+        assert!(!dnd.is_active(at(2, 2)));
+        assert!(!dnd.is_active(at(2, 12)));
+        assert!(dnd.is_active(at(2, 22)));
+
+        dnd.set(at(3, 12), true);
+        assert!(dnd.is_active(at(3, 20)));
+        assert!(dnd.is_active(at(3, 23)));
+        assert!(!dnd.is_active(at(4, 8)));
+    }
+}
@@ -0,0 +1,371 @@
+use crate::automation::{self, default_ntfy_server};
+use crate::bus::{CameraEvent, EventBus};
+use crate::email::{self, EmailConfig};
+use crate::event::{Event, EventDetails, EventKind};
+use crate::reolink::{ReolinkClient, ReolinkConfig};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DoorbellConfig {
+    pub host: String,
+    pub user: String,
+    pub password: String,
+    pub camera: String,
+    #[serde(default = "default_poll_interval_active", alias = "poll_interval_ms")]
+    pub poll_interval_active: u64,
+    #[serde(default = "default_poll_interval_idle")]
+    pub poll_interval_idle: u64,
+    #[serde(default = "default_wake_on_visitor")]
+    pub wake_on_visitor: WakeAction,
+    #[serde(default)]
+    pub wake_on_person: WakeAction,
+    #[serde(default)]
+    pub wake_on_motion: WakeAction,
+    #[serde(default = "default_event_cooldown_secs")]
+    pub event_cooldown_secs: u64,
+    #[serde(default = "default_unreachable_alert_mins")]
+    pub unreachable_alert_mins: u64,
+    #[serde(default)]
+    pub alert_email: bool,
+    pub alert_ntfy_topic: Option<String>,
+    #[serde(default = "default_ntfy_server")]
+    pub alert_ntfy_server: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(from = "WakeSetting")]
+pub enum WakeAction {
+    #[default]
+    Ignore,
+    Wake,
+    Switch,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WakeSetting {
+    Flag(bool),
+    Action(WakeActionName),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WakeActionName {
+    Ignore,
+    Wake,
+    Switch,
+}
+
+impl From<WakeSetting> for WakeAction {
+    fn from(setting: WakeSetting) -> Self {
+        match setting {
+            WakeSetting::Flag(false) | WakeSetting::Action(WakeActionName::Ignore) => {
+                WakeAction::Ignore
+            }
+            WakeSetting::Action(WakeActionName::Wake) => WakeAction::Wake,
+            WakeSetting::Flag(true) | WakeSetting::Action(WakeActionName::Switch) => {
+                WakeAction::Switch
+            }
+        }
+    }
+}
+
+impl DoorbellConfig {
+    pub fn wake_action(&self, kind: EventKind) -> WakeAction {
+        match kind {
+            EventKind::Visitor => self.wake_on_visitor,
+            EventKind::Person => self.wake_on_person,
+            EventKind::Motion => self.wake_on_motion,
+            EventKind::LoudNoise => WakeAction::Ignore,
+        }
+    }
+}
+
+fn default_wake_on_visitor() -> WakeAction {
+    WakeAction::Switch
+}
+
+const POLL_JITTER: f64 = 0.2;
+const RECENT_EVENT_WINDOW: Duration = Duration::from_secs(60);
+
+fn default_poll_interval_active() -> u64 {
+    300
+}
+
+fn default_poll_interval_idle() -> u64 {
+    2000
+}
+
+fn default_event_cooldown_secs() -> u64 {
+    30
+}
+
+fn default_unreachable_alert_mins() -> u64 {
+    5
+}
+
+#[derive(Debug, Clone)]
+pub struct DoorbellHealth {
+    pub last_success: Option<DateTime<Local>>,
+    pub consecutive_failures: u32,
+    pub unreachable: bool,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct AlarmState {
+    #[serde(default)]
+    alarm_state: u8,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct AiStates {
+    #[serde(default)]
+    people: AlarmState,
+    #[serde(default)]
+    vehicle: AlarmState,
+    #[serde(default)]
+    dog_cat: AlarmState,
+}
+
+impl AiStates {
+    fn active(&self) -> Vec<String> {
+        [
+            ("people", &self.people),
+            ("vehicle", &self.vehicle),
+            ("dog_cat", &self.dog_cat),
+        ]
+        .into_iter()
+        .filter(|(_, state)| state.alarm_state == 1)
+        .map(|(name, _)| name.to_string())
+        .collect()
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct EventStates {
+    #[serde(default)]
+    ai: AiStates,
+    #[serde(default)]
+    md: AlarmState,
+    #[serde(default)]
+    visitor: AlarmState,
+}
+
+fn jittered(interval: Duration) -> Duration {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    let factor = 1.0 - POLL_JITTER + 2.0 * POLL_JITTER * (nanos % 1000) as f64 / 1000.0;
+    interval.mul_f64(factor)
+}
+
+#[derive(Clone)]
+pub struct PollRate {
+    display_awake: Arc<AtomicBool>,
+}
+
+impl PollRate {
+    pub fn set_display_awake(&self, awake: bool) {
+        self.display_awake.store(awake, Ordering::Relaxed);
+    }
+}
+
+pub struct DoorbellMonitor {
+    config: DoorbellConfig,
+    bus: EventBus,
+    email: Option<EmailConfig>,
+    client: ReolinkClient,
+    poll_rate: PollRate,
+}
+
+impl DoorbellMonitor {
+    pub fn spawn(config: DoorbellConfig, bus: EventBus, email: Option<EmailConfig>) -> PollRate {
+        let poll_rate = PollRate {
+            display_awake: Arc::new(AtomicBool::new(true)),
+        };
+
+        let client = ReolinkClient::new(ReolinkConfig {
+            host: config.host.clone(),
+            user: config.user.clone(),
+            password: config.password.clone(),
+            channel: 0,
+            set_day_night: false,
+        });
+
+        let monitor = DoorbellMonitor {
+            client,
+            config,
+            bus,
+            email,
+            poll_rate: poll_rate.clone(),
+        };
+
+        thread::spawn(move || monitor.listen_loop());
+        poll_rate
+    }
+
+    fn poll_interval(&self, last_event: Option<Instant>) -> Duration {
+        let recent_event = last_event.is_some_and(|time| time.elapsed() < RECENT_EVENT_WINDOW);
+        let interval = if recent_event || self.poll_rate.display_awake.load(Ordering::Relaxed) {
+            self.config.poll_interval_active
+        } else {
+            self.config.poll_interval_idle
+        };
+        jittered(Duration::from_millis(interval))
+    }
+
+    fn listen_loop(&self) {
+        let mut visitor = false;
+        let mut person = false;
+        let mut motion = false;
+        let mut last_event = None;
+        let mut last_sent: HashMap<EventKind, Instant> = HashMap::new();
+        let cooldown = Duration::from_secs(self.config.event_cooldown_secs);
+        let mut debounce = |kind: EventKind| {
+            let now = Instant::now();
+            if last_sent
+                .get(&kind)
+                .is_some_and(|sent| now.duration_since(*sent) < cooldown)
+            {
+                return false;
+            }
+            last_sent.insert(kind, now);
+            true
+        };
+
+        let mut health = DoorbellHealth {
+            last_success: None,
+            consecutive_failures: 0,
+            unreachable: false,
+        };
+        let mut reachable_since = Instant::now();
+        let alert_after = Duration::from_secs(self.config.unreachable_alert_mins * 60);
+
+        loop {
+            match self.poll() {
+                Ok(states) => {
+                    if health.last_success.is_none() || health.consecutive_failures > 0 {
+                        if health.unreachable {
+                            self.alert("Sonnette de nouveau joignable");
+                        }
+                        health.consecutive_failures = 0;
+                        health.unreachable = false;
+                        health.last_success = Some(Local::now());
+                        self.bus
+                            .publish(CameraEvent::DoorbellStatus(health.clone()));
+                    } else {
+                        health.last_success = Some(Local::now());
+                    }
+                    reachable_since = Instant::now();
+
+                    let visitor_now = states.visitor.alarm_state == 1;
+                    let person_now = states.ai.people.alarm_state == 1;
+                    let motion_now = states.md.alarm_state == 1;
+
+                    if visitor_now && !visitor && debounce(EventKind::Visitor) {
+                        let details = self.fetch_details(&states);
+                        self.send(EventKind::Visitor, Some(Arc::new(details)));
+                    }
+                    if person_now && !person && debounce(EventKind::Person) {
+                        self.send(EventKind::Person, None);
+                    }
+                    if motion_now && !motion && debounce(EventKind::Motion) {
+                        self.send(EventKind::Motion, None);
+                    }
+
+                    if visitor_now || person_now || motion_now {
+                        last_event = Some(Instant::now());
+                    }
+                    visitor = visitor_now;
+                    person = person_now;
+                    motion = motion_now;
+                }
+                Err(e) => {
+                    eprintln!("Erreur lors de l'interrogation de la sonnette : {}", e);
+                    health.consecutive_failures += 1;
+                    if !health.unreachable && reachable_since.elapsed() >= alert_after {
+                        health.unreachable = true;
+                        self.alert(&format!(
+                            "Sonnette injoignable depuis plus de {} minutes : {}",
+                            self.config.unreachable_alert_mins, e
+                        ));
+                    }
+                    self.bus
+                        .publish(CameraEvent::DoorbellStatus(health.clone()));
+                    thread::sleep(Duration::from_secs(5));
+                }
+            }
+
+            thread::sleep(self.poll_interval(last_event));
+        }
+    }
+
+    fn alert(&self, message: &str) {
+        eprintln!("{}", message);
+        let subject = format!("Sonnette ({})", self.config.camera);
+
+        if self.config.alert_email
+            && let Some(email) = &self.email
+            && let Err(e) = email::send_text(email, &subject, message)
+        {
+            eprintln!("Erreur lors de l'envoi de l'alerte par email : {}", e);
+        }
+
+        if let Some(topic) = &self.config.alert_ntfy_topic
+            && let Err(e) = automation::post_ntfy(
+                &self.config.alert_ntfy_server,
+                topic,
+                &subject,
+                message.to_string(),
+            )
+        {
+            eprintln!("Erreur lors de l'envoi de l'alerte ntfy : {}", e);
+        }
+    }
+
+    fn fetch_details(&self, states: &EventStates) -> EventDetails {
+        let snapshot = match self.client.snap() {
+            Ok(jpeg) => Some(jpeg),
+            Err(e) => {
+                eprintln!("Impossible de récupérer l'image de la sonnette : {}", e);
+                None
+            }
+        };
+        let sensitivity = match self.client.motion_sensitivity() {
+            Ok(sensitivity) => sensitivity,
+            Err(e) => {
+                eprintln!("Impossible de lire la sensibilité de la sonnette : {}", e);
+                None
+            }
+        };
+
+        EventDetails {
+            snapshot,
+            ai_types: states.ai.active(),
+            sensitivity,
+        }
+    }
+
+    fn send(&self, kind: EventKind, details: Option<Arc<EventDetails>>) {
+        self.bus.publish(CameraEvent::Detected(Event {
+            camera: self.config.camera.clone(),
+            kind,
+            details,
+        }));
+    }
+
+    fn poll(&self) -> Result<EventStates, ureq::Error> {
+        let value = self
+            .client
+            .command("GetEvents", serde_json::json!({ "channel": 0 }))?;
+        serde_json::from_value(value)
+            .map_err(|e| ureq::Error::Other(format!("réponse GetEvents invalide : {}", e).into()))
+    }
+}
@@ -0,0 +1,171 @@
+use crate::event::{Event, EventKind};
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::Deserialize;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EmailConfig {
+    pub smtp_server: String,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    pub username: String,
+    pub password: String,
+    pub from: String,
+    pub to: Vec<String>,
+    #[serde(default = "default_events")]
+    pub events: Vec<EventKind>,
+    #[serde(default = "default_min_interval_secs")]
+    pub min_interval_secs: u64,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_events() -> Vec<EventKind> {
+    vec![EventKind::Visitor]
+}
+
+fn default_min_interval_secs() -> u64 {
+    60
+}
+
+pub struct EmailNotifier {
+    config: EmailConfig,
+    last_sent: Option<Instant>,
+}
+
+impl EmailNotifier {
+    pub fn new(config: EmailConfig) -> Self {
+        EmailNotifier {
+            config,
+            last_sent: None,
+        }
+    }
+
+    pub fn wants(&self, event: EventKind) -> bool {
+        if !self.config.events.contains(&event) {
+            return false;
+        }
+
+        match self.last_sent {
+            Some(last) => last.elapsed() >= Duration::from_secs(self.config.min_interval_secs),
+            None => true,
+        }
+    }
+
+    pub fn send(&mut self, event: &Event, frame: Option<Vec<u8>>) {
+        self.last_sent = Some(Instant::now());
+
+        let config = self.config.clone();
+        let event = event.clone();
+
+        thread::spawn(move || {
+            if let Err(e) = send_email(&config, &event, frame) {
+                eprintln!("Erreur lors de l'envoi de l'e-mail : {}", e);
+            }
+        });
+    }
+}
+
+pub fn encode_jpeg(frame: Vec<u8>) -> Option<Vec<u8>> {
+    let buffer =
+        image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(crate::WIDTH, crate::HEIGHT, frame)?;
+    let rgb = image::DynamicImage::ImageRgba8(buffer).to_rgb8();
+
+    let mut jpeg = std::io::Cursor::new(Vec::new());
+    rgb.write_to(&mut jpeg, image::ImageFormat::Jpeg).ok()?;
+    Some(jpeg.into_inner())
+}
+
+fn send_email(
+    config: &EmailConfig,
+    event: &Event,
+    frame: Option<Vec<u8>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let timestamp = chrono::Local::now();
+    let subject = format!("{} ({})", event.kind.label(), event.camera);
+    let mut text = format!(
+        "{} sur la caméra {} le {}.",
+        event.kind.label(),
+        event.camera,
+        timestamp.format("%d/%m/%Y à %H:%M:%S")
+    );
+
+    let details = event.details.as_deref();
+    if let Some(summary) = details
+        .map(|details| details.summary())
+        .filter(|summary| !summary.is_empty())
+    {
+        text.push_str(&format!("\n{}", summary));
+    }
+
+    let mut body = MultiPart::mixed().singlepart(SinglePart::plain(text));
+    let jpeg = details
+        .and_then(|details| details.snapshot.clone())
+        .or_else(|| frame.and_then(encode_jpeg));
+    if let Some(jpeg) = jpeg {
+        let filename = format!("{}.jpg", timestamp.format("%Y-%m-%d_%H-%M-%S"));
+        body = body
+            .singlepart(Attachment::new(filename).body(jpeg, ContentType::parse("image/jpeg")?));
+    }
+
+    deliver(config, subject, body)
+}
+
+pub fn send_text(
+    config: &EmailConfig,
+    subject: &str,
+    text: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let body = MultiPart::mixed().singlepart(SinglePart::plain(text.to_string()));
+    deliver(config, subject.to_string(), body)
+}
+
+pub fn send_file(
+    config: &EmailConfig,
+    subject: &str,
+    text: &str,
+    path: &std::path::Path,
+    content_type: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let body = MultiPart::mixed()
+        .singlepart(SinglePart::plain(text.to_string()))
+        .singlepart(
+            Attachment::new(filename).body(std::fs::read(path)?, ContentType::parse(content_type)?),
+        );
+    deliver(config, subject.to_string(), body)
+}
+
+fn deliver(
+    config: &EmailConfig,
+    subject: String,
+    body: MultiPart,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut builder = Message::builder()
+        .from(config.from.parse()?)
+        .subject(subject);
+    for address in &config.to {
+        builder = builder.to(address.parse()?);
+    }
+    let message = builder.multipart(body)?;
+
+    let mailer = SmtpTransport::starttls_relay(&config.smtp_server)?
+        .port(config.smtp_port)
+        .credentials(Credentials::new(
+            config.username.clone(),
+            config.password.clone(),
+        ))
+        .build();
+
+    mailer.send(&message)?;
+    Ok(())
+}
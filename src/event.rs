@@ -0,0 +1,78 @@
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Visitor,
+    Person,
+    Motion,
+    LoudNoise,
+}
+
+impl EventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::Visitor => "visitor",
+            EventKind::Person => "person",
+            EventKind::Motion => "motion",
+            EventKind::LoudNoise => "loud_noise",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EventKind::Visitor => "On sonne à la porte",
+            EventKind::Person => "Personne détectée",
+            EventKind::Motion => "Mouvement détecté",
+            EventKind::LoudNoise => "Bruit fort détecté",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "visitor" => Some(EventKind::Visitor),
+            "person" => Some(EventKind::Person),
+            "motion" => Some(EventKind::Motion),
+            "loud_noise" => Some(EventKind::LoudNoise),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub camera: String,
+    pub kind: EventKind,
+    pub details: Option<Arc<EventDetails>>,
+}
+
+#[derive(Debug, Default)]
+pub struct EventDetails {
+    pub snapshot: Option<Vec<u8>>,
+    pub ai_types: Vec<String>,
+    pub sensitivity: Option<u32>,
+}
+
+impl EventDetails {
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.ai_types.is_empty() {
+            parts.push(format!("détection IA : {}", self.ai_types.join(", ")));
+        }
+        if let Some(sensitivity) = self.sensitivity {
+            parts.push(format!("sensibilité : {}", sensitivity));
+        }
+        parts.join(", ")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RemoteCommand {
+    SwitchCamera(String),
+    Snapshot,
+    PeerCamera(String),
+    PeerDoorbell(String),
+    CameraPower { camera: String, on: bool },
+    DoNotDisturb(bool),
+}
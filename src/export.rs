@@ -0,0 +1,331 @@
+use crate::email::{self, EmailConfig};
+use crate::media_index::{MediaKind, MediaRecord, sanitize_camera_name};
+use chrono::Local;
+use ffmpeg_next::{self as ffmpeg, Rational};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+const EXPORT_BIT_RATE: usize = 4_000_000;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct PrivacyMask {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl PrivacyMask {
+    fn pixels(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let scale = |value: f32, size: u32| (value.clamp(0.0, 1.0) * size as f32).round() as u32;
+        let x = scale(self.x, width);
+        let y = scale(self.y, height);
+        (
+            x,
+            y,
+            scale(self.width, width).min(width - x),
+            scale(self.height, height).min(height - y),
+        )
+    }
+
+    fn filter(&self) -> String {
+        format!(
+            "drawbox=x=iw*{}:y=ih*{}:w=iw*{}:h=ih*{}:color=black:t=fill",
+            self.x.clamp(0.0, 1.0),
+            self.y.clamp(0.0, 1.0),
+            self.width.clamp(0.0, 1.0),
+            self.height.clamp(0.0, 1.0)
+        )
+    }
+}
+
+pub fn spawn(
+    items: Vec<(MediaRecord, Vec<PrivacyMask>)>,
+    export_dir: PathBuf,
+    email: Option<EmailConfig>,
+) {
+    thread::spawn(move || {
+        if let Err(e) = std::fs::create_dir_all(&export_dir) {
+            eprintln!("Impossible de créer le dossier d'export : {}", e);
+            return;
+        }
+
+        for (record, masks) in items {
+            export(&record, &export_dir, &masks, email.as_ref());
+        }
+    });
+}
+
+fn export(
+    record: &MediaRecord,
+    export_dir: &Path,
+    masks: &[PrivacyMask],
+    email: Option<&EmailConfig>,
+) {
+    let (extension, content_type, label) = match record.kind {
+        MediaKind::Clip => ("mp4", "video/mp4", "Vidéo"),
+        MediaKind::Snapshot => ("jpg", "image/jpeg", "Image"),
+    };
+    let path = export_dir.join(format!(
+        "{}_{}.{}",
+        sanitize_camera_name(&record.camera),
+        record.timestamp.format("%Y-%m-%d_%H-%M-%S"),
+        extension
+    ));
+
+    let result: Result<(), Box<dyn std::error::Error>> = match record.kind {
+        MediaKind::Clip => export_clip(record, &path, masks).map_err(Into::into),
+        MediaKind::Snapshot => export_snapshot(record, &path, masks),
+    };
+    if let Err(e) = result {
+        eprintln!(
+            "Erreur lors de l'export de {} : {}",
+            record.path.display(),
+            e
+        );
+        return;
+    }
+    println!("{} exportée : {}", label, path.display());
+
+    if let Some(config) = email {
+        let subject = format!("{} de {}", label, record.camera);
+        let text = format!(
+            "Enregistrement de la caméra {} du {}.",
+            record.camera,
+            record.timestamp.format("%d/%m/%Y à %H:%M:%S")
+        );
+        if let Err(e) = email::send_file(config, &subject, &text, &path, content_type) {
+            eprintln!("Erreur lors de l'envoi de l'export : {}", e);
+        }
+    }
+}
+
+fn export_snapshot(
+    record: &MediaRecord,
+    output: &Path,
+    masks: &[PrivacyMask],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut image = image::open(&record.path)?.to_rgb8();
+    let (width, height) = image.dimensions();
+
+    for mask in masks {
+        let (x, y, mask_width, mask_height) = mask.pixels(width, height);
+        for row in y..y + mask_height {
+            for column in x..x + mask_width {
+                image.put_pixel(column, row, image::Rgb([0, 0, 0]));
+            }
+        }
+    }
+
+    image.save_with_format(output, image::ImageFormat::Jpeg)?;
+    Ok(())
+}
+
+fn escape(value: &str, special: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if special.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn annotation_text(record: &MediaRecord) -> String {
+    let mut text = escape(&record.camera, "\\%");
+    if let Some(start) = record.timestamp.and_local_timezone(Local).earliest() {
+        text.push_str(&format!(
+            " — %{{pts:localtime:{}:%d/%m/%Y %H\\:%M\\:%S}}",
+            start.timestamp()
+        ));
+    }
+    if let Some(event) = record.event {
+        text.push_str(" — ");
+        text.push_str(&escape(event.label(), "\\%"));
+    }
+
+    let option = escape(&text, "\\':");
+    escape(&option, "\\'[],;")
+}
+
+fn annotation_graph(
+    decoder: &ffmpeg::decoder::Video,
+    time_base: Rational,
+    record: &MediaRecord,
+    masks: &[PrivacyMask],
+) -> Result<ffmpeg::filter::Graph, ffmpeg::Error> {
+    let pixel_format = decoder
+        .format()
+        .descriptor()
+        .ok_or(ffmpeg::Error::InvalidData)?
+        .name();
+    let args = format!(
+        "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect=1/1",
+        decoder.width(),
+        decoder.height(),
+        pixel_format,
+        time_base.numerator(),
+        time_base.denominator()
+    );
+
+    let mut graph = ffmpeg::filter::Graph::new();
+    let buffer = ffmpeg::filter::find("buffer").ok_or(ffmpeg::Error::FilterNotFound)?;
+    let buffersink = ffmpeg::filter::find("buffersink").ok_or(ffmpeg::Error::FilterNotFound)?;
+    graph.add(&buffer, "in", &args)?;
+    graph.add(&buffersink, "out", "")?;
+
+    let mut filters: Vec<String> = masks.iter().map(PrivacyMask::filter).collect();
+    filters.push(format!(
+        "drawtext=text={}:x=20:y=h-th-20:fontsize=h/24:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw=12",
+        annotation_text(record)
+    ));
+    filters.push("format=yuv420p".to_string());
+    let spec = filters.join(",");
+    graph.output("in", 0)?.input("out", 0)?.parse(&spec)?;
+    graph.validate()?;
+    Ok(graph)
+}
+
+struct Exporter {
+    decoder: ffmpeg::decoder::Video,
+    graph: ffmpeg::filter::Graph,
+    encoder: ffmpeg::encoder::video::Encoder,
+    octx: ffmpeg::format::context::Output,
+    encoder_time_base: Rational,
+    stream_time_base: Rational,
+    first_pts: Option<i64>,
+}
+
+impl Exporter {
+    fn receive_decoded(&mut self) -> Result<(), ffmpeg::Error> {
+        let mut frame = ffmpeg::util::frame::video::Video::empty();
+        while self.decoder.receive_frame(&mut frame).is_ok() {
+            let pts = frame.timestamp().unwrap_or(0);
+            let first_pts = *self.first_pts.get_or_insert(pts);
+            frame.set_pts(Some(pts - first_pts));
+
+            self.graph
+                .get("in")
+                .ok_or(ffmpeg::Error::Bug)?
+                .source()
+                .add(&frame)?;
+            self.receive_filtered()?;
+        }
+        Ok(())
+    }
+
+    fn receive_filtered(&mut self) -> Result<(), ffmpeg::Error> {
+        let mut annotated = ffmpeg::util::frame::video::Video::empty();
+        loop {
+            let received = self
+                .graph
+                .get("out")
+                .ok_or(ffmpeg::Error::Bug)?
+                .sink()
+                .frame(&mut annotated);
+            if received.is_err() {
+                return Ok(());
+            }
+            self.encoder.send_frame(&annotated)?;
+            self.receive_encoded()?;
+        }
+    }
+
+    fn receive_encoded(&mut self) -> Result<(), ffmpeg::Error> {
+        let mut packet = ffmpeg::Packet::empty();
+        while self.encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(0);
+            packet.rescale_ts(self.encoder_time_base, self.stream_time_base);
+            packet.write_interleaved(&mut self.octx)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ffmpeg::Error> {
+        self.decoder.send_eof()?;
+        self.receive_decoded()?;
+        self.graph
+            .get("in")
+            .ok_or(ffmpeg::Error::Bug)?
+            .source()
+            .flush()?;
+        self.receive_filtered()?;
+        self.encoder.send_eof()?;
+        self.receive_encoded()?;
+        self.octx.write_trailer()
+    }
+}
+
+fn export_clip(
+    record: &MediaRecord,
+    output: &Path,
+    masks: &[PrivacyMask],
+) -> Result<(), ffmpeg::Error> {
+    let mut ictx = ffmpeg::format::input(&record.path)?;
+    let input = ictx
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or(ffmpeg::Error::StreamNotFound)?;
+    let video_index = input.index();
+    let time_base = input.time_base();
+    let frame_rate = input.avg_frame_rate();
+    let decoder = ffmpeg::codec::context::Context::from_parameters(input.parameters())?
+        .decoder()
+        .video()?;
+
+    let graph = annotation_graph(&decoder, time_base, record, masks)?;
+
+    let mut octx = ffmpeg::format::output(output)?;
+    let codec = ffmpeg::encoder::find(ffmpeg::codec::Id::H264)
+        .or_else(|| ffmpeg::encoder::find(ffmpeg::codec::Id::MPEG4))
+        .ok_or(ffmpeg::Error::EncoderNotFound)?;
+
+    let mut encoder = ffmpeg::codec::context::Context::new_with_codec(codec)
+        .encoder()
+        .video()?;
+    encoder.set_width(decoder.width());
+    encoder.set_height(decoder.height());
+    encoder.set_format(ffmpeg::format::Pixel::YUV420P);
+    encoder.set_time_base(time_base);
+    if frame_rate.numerator() > 0 {
+        encoder.set_frame_rate(Some(frame_rate));
+    }
+    encoder.set_bit_rate(EXPORT_BIT_RATE);
+    if octx
+        .format()
+        .flags()
+        .contains(ffmpeg::format::Flags::GLOBAL_HEADER)
+    {
+        encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+    }
+    let encoder = encoder.open_as(codec)?;
+
+    let mut stream = octx.add_stream(codec)?;
+    stream.set_parameters(&encoder);
+    octx.write_header()?;
+    let stream_time_base = octx
+        .stream(0)
+        .map(|stream| stream.time_base())
+        .unwrap_or(time_base);
+
+    let mut exporter = Exporter {
+        decoder,
+        graph,
+        encoder,
+        octx,
+        encoder_time_base: time_base,
+        stream_time_base,
+        first_pts: None,
+    };
+
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != video_index {
+            continue;
+        }
+        exporter.decoder.send_packet(&packet)?;
+        exporter.receive_decoded()?;
+    }
+    exporter.finish()
+}
@@ -0,0 +1,87 @@
+use crate::bus::{CameraEvent, EventBus};
+use crate::decoder::VideoFrame;
+use crate::pacing::{FrameBudget, FramePacer};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct Frames {
+    active_url: String,
+    pacer: FramePacer,
+    latest: HashMap<String, (Instant, Arc<VideoFrame>)>,
+}
+
+#[derive(Clone)]
+pub struct FrameStore {
+    frames: Arc<Mutex<Frames>>,
+    budget: FrameBudget,
+}
+
+impl FrameStore {
+    pub fn spawn(bus: &EventBus, drop_late_frames: bool, budget: FrameBudget) -> Self {
+        let store = FrameStore {
+            frames: Arc::new(Mutex::new(Frames {
+                active_url: String::new(),
+                pacer: FramePacer::new(drop_late_frames, budget.clone()),
+                latest: HashMap::new(),
+            })),
+            budget,
+        };
+
+        let events = bus.subscribe_filtered(|event| matches!(event, CameraEvent::FrameReady(_)));
+        let ingest = store.clone();
+        thread::spawn(move || {
+            for event in events {
+                if let CameraEvent::FrameReady(frame) = event {
+                    ingest.push(frame);
+                }
+            }
+        });
+
+        store
+    }
+
+    fn push(&self, frame: Arc<VideoFrame>) {
+        let mut frames = self.frames.lock().unwrap();
+        frames
+            .latest
+            .insert(frame.camera.clone(), (Instant::now(), frame.clone()));
+
+        if frame.url == frames.active_url {
+            frames.pacer.push(frame);
+        } else {
+            self.budget.release(1);
+        }
+    }
+
+    pub fn set_active(&self, url: &str) {
+        let mut frames = self.frames.lock().unwrap();
+        frames.active_url = url.to_string();
+        frames.pacer.clear();
+    }
+
+    pub fn clear(&self) {
+        self.frames.lock().unwrap().pacer.clear();
+    }
+
+    pub fn next_frame(&self) -> Option<Arc<VideoFrame>> {
+        self.frames.lock().unwrap().pacer.next_frame()
+    }
+
+    pub fn next_due_in(&self) -> Option<Duration> {
+        self.frames
+            .lock()
+            .unwrap()
+            .pacer
+            .next_due_in(Instant::now())
+    }
+
+    pub fn take_dropped(&self) -> u64 {
+        self.frames.lock().unwrap().pacer.take_dropped()
+    }
+
+    pub fn latest(&self, camera: &str) -> Option<(Instant, Arc<VideoFrame>)> {
+        self.frames.lock().unwrap().latest.get(camera).cloned()
+    }
+}
@@ -0,0 +1,282 @@
+use crate::automation::{self, default_ntfy_server};
+use crate::bus::{CameraEvent, EventBus};
+use crate::decoder::StreamStatus;
+use crate::email::{self, EmailConfig};
+use chrono::{Local, NaiveDate, NaiveTime};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HealthReportConfig {
+    #[serde(default = "default_report_time")]
+    pub time: NaiveTime,
+    pub directory: Option<String>,
+    #[serde(default)]
+    pub email: bool,
+    pub ntfy_topic: Option<String>,
+    #[serde(default = "default_ntfy_server")]
+    pub ntfy_server: String,
+}
+
+fn default_report_time() -> NaiveTime {
+    NaiveTime::from_hms_opt(8, 0, 0).unwrap()
+}
+
+#[derive(Default)]
+struct CameraHealth {
+    reconnects: u32,
+    errors: u32,
+    playing: bool,
+}
+
+#[derive(Default)]
+struct HealthCounters {
+    cameras: BTreeMap<String, CameraHealth>,
+    events: BTreeMap<&'static str, u32>,
+    dropped_frames: u64,
+    low_power: Duration,
+}
+
+#[derive(Clone)]
+pub struct HealthMonitor {
+    counters: Arc<Mutex<HealthCounters>>,
+}
+
+pub fn disk_space(path: &Path) -> Option<(u64, u64)> {
+    let path = path.canonicalize().ok()?;
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| (disk.available_space(), disk.total_space()))
+}
+
+fn directory_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => directory_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+fn gigabytes(bytes: u64) -> f64 {
+    bytes as f64 / 1_000_000_000.0
+}
+
+impl HealthMonitor {
+    pub fn spawn(
+        config: HealthReportConfig,
+        camera_names: HashMap<String, String>,
+        capture_path: String,
+        email: Option<EmailConfig>,
+        bus: &EventBus,
+    ) -> Self {
+        let monitor = HealthMonitor {
+            counters: Arc::new(Mutex::new(HealthCounters::default())),
+        };
+
+        let events = bus.subscribe();
+        let collector = monitor.clone();
+        thread::spawn(move || {
+            for event in events {
+                collector.record(&event, &camera_names);
+            }
+        });
+
+        let reporter = monitor.clone();
+        thread::spawn(move || reporter.report_loop(config, capture_path, email));
+
+        monitor
+    }
+
+    pub fn add_dropped_frames(&self, count: u64) {
+        if count > 0 {
+            self.counters.lock().unwrap().dropped_frames += count;
+        }
+    }
+
+    pub fn add_low_power(&self, duration: Duration) {
+        self.counters.lock().unwrap().low_power += duration;
+    }
+
+    fn record(&self, event: &CameraEvent, camera_names: &HashMap<String, String>) {
+        let mut counters = self.counters.lock().unwrap();
+
+        match event {
+            CameraEvent::Detected(event) => {
+                *counters.events.entry(event.kind.label()).or_default() += 1;
+            }
+            CameraEvent::StreamStatus(update) => {
+                let Some(camera) = camera_names.get(&update.url) else {
+                    return;
+                };
+                let health = counters.cameras.entry(camera.clone()).or_default();
+
+                match update.status {
+                    StreamStatus::Connecting if health.playing => {
+                        health.reconnects += 1;
+                        health.playing = false;
+                    }
+                    StreamStatus::Playing { .. } => health.playing = true,
+                    StreamStatus::Error(_) => {
+                        health.errors += 1;
+                        health.playing = false;
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn report_loop(
+        &self,
+        config: HealthReportConfig,
+        capture_path: String,
+        email: Option<EmailConfig>,
+    ) {
+        let started = Instant::now();
+        let now = Local::now();
+        let mut last_report: NaiveDate = if now.time() >= config.time {
+            now.date_naive()
+        } else {
+            now.date_naive().pred_opt().unwrap_or(now.date_naive())
+        };
+
+        loop {
+            thread::sleep(Duration::from_secs(60));
+
+            let now = Local::now();
+            if now.date_naive() == last_report || now.time() < config.time {
+                continue;
+            }
+            last_report = now.date_naive();
+
+            let report = self.build_report(started.elapsed(), Path::new(&capture_path));
+            self.publish(&config, &capture_path, email.as_ref(), &report);
+        }
+    }
+
+    fn build_report(&self, uptime: Duration, capture_path: &Path) -> String {
+        let mut counters = self.counters.lock().unwrap();
+        let mut report = String::new();
+
+        let minutes = uptime.as_secs() / 60;
+        let _ = writeln!(
+            report,
+            "Rapport de santé du {}",
+            Local::now().format("%d/%m/%Y")
+        );
+        let _ = writeln!(
+            report,
+            "Fonctionnement depuis : {} j {} h {} min",
+            minutes / 1440,
+            minutes / 60 % 24,
+            minutes % 60
+        );
+        match disk_space(capture_path) {
+            Some((available, total)) => {
+                let _ = writeln!(
+                    report,
+                    "Espace disque : {:.1} Go libres sur {:.1} Go (captures : {:.1} Go)",
+                    gigabytes(available),
+                    gigabytes(total),
+                    gigabytes(directory_size(capture_path))
+                );
+            }
+            None => {
+                let _ = writeln!(report, "Espace disque : inconnu");
+            }
+        }
+        let _ = writeln!(report, "Images abandonnées : {}", counters.dropped_frames);
+        let low_power = counters.low_power.as_secs() / 60;
+        let _ = writeln!(
+            report,
+            "Écran en veille : {} h {} min",
+            low_power / 60,
+            low_power % 60
+        );
+
+        let _ = writeln!(report, "\nCaméras :");
+        for (camera, health) in &counters.cameras {
+            let _ = writeln!(
+                report,
+                "- {} : {} reconnexion(s), {} erreur(s)",
+                camera, health.reconnects, health.errors
+            );
+        }
+
+        let _ = writeln!(report, "\nÉvénements :");
+        if counters.events.is_empty() {
+            let _ = writeln!(report, "- aucun");
+        }
+        for (label, count) in &counters.events {
+            let _ = writeln!(report, "- {} : {}", label, count);
+        }
+
+        for health in counters.cameras.values_mut() {
+            health.reconnects = 0;
+            health.errors = 0;
+        }
+        counters.events.clear();
+        counters.dropped_frames = 0;
+        counters.low_power = Duration::ZERO;
+
+        report
+    }
+
+    fn publish(
+        &self,
+        config: &HealthReportConfig,
+        capture_path: &str,
+        email: Option<&EmailConfig>,
+        report: &str,
+    ) {
+        let directory = config
+            .directory
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| Path::new(capture_path).join("reports"));
+        let path = directory.join(format!("sante_{}.txt", Local::now().format("%Y-%m-%d")));
+
+        if let Err(e) =
+            std::fs::create_dir_all(&directory).and_then(|_| std::fs::write(&path, report))
+        {
+            eprintln!(
+                "Impossible d'écrire le rapport de santé {} : {}",
+                path.display(),
+                e
+            );
+        }
+
+        let subject = "Rapport de santé du kiosque";
+        if config.email
+            && let Some(email) = email
+            && let Err(e) = email::send_text(email, subject, report)
+        {
+            eprintln!("Erreur lors de l'envoi du rapport par e-mail : {}", e);
+        }
+
+        if let Some(topic) = &config.ntfy_topic
+            && let Err(e) =
+                automation::post_ntfy(&config.ntfy_server, topic, subject, report.to_string())
+        {
+            eprintln!("Erreur lors de l'envoi du rapport ntfy : {}", e);
+        }
+    }
+}
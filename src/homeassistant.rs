@@ -0,0 +1,184 @@
+use crate::bus::{CameraEvent, EventBus};
+use crate::event::{Event, RemoteCommand};
+use serde::Deserialize;
+use std::thread;
+use std::time::Duration;
+use tungstenite::Message;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HomeAssistantConfig {
+    pub url: String,
+    pub token: String,
+    #[serde(default = "default_event_type")]
+    pub event_type: String,
+    #[serde(default = "default_command_event")]
+    pub command_event: String,
+}
+
+fn default_event_type() -> String {
+    "security_event".to_string()
+}
+
+fn default_command_event() -> String {
+    "security_command".to_string()
+}
+
+#[derive(Deserialize, Debug)]
+struct CommandData {
+    command: String,
+    camera: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct HomeAssistant {
+    config: HomeAssistantConfig,
+    agent: ureq::Agent,
+}
+
+fn entity_id(camera: &str) -> String {
+    let name: String = camera
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("camera.security_{}", name)
+}
+
+impl HomeAssistant {
+    pub fn spawn(config: HomeAssistantConfig, cameras: Vec<String>, bus: EventBus) {
+        let agent = ureq::Agent::new_with_config(
+            ureq::Agent::config_builder()
+                .timeout_global(Some(Duration::from_secs(5)))
+                .build(),
+        );
+        let home_assistant = HomeAssistant { config, agent };
+
+        let publisher = home_assistant.clone();
+        thread::spawn(move || {
+            for camera in &cameras {
+                publisher.publish_camera(camera, None);
+            }
+        });
+
+        let events = bus.subscribe();
+        let notifier = home_assistant.clone();
+        thread::spawn(move || {
+            for event in events {
+                if let CameraEvent::Detected(event) = event {
+                    notifier.fire_event(&event);
+                }
+            }
+        });
+
+        thread::spawn(move || home_assistant.listen_loop(bus));
+    }
+
+    fn post(&self, path: &str, body: serde_json::Value) -> Result<(), ureq::Error> {
+        let url = format!("{}{}", self.config.url.trim_end_matches('/'), path);
+        self.agent
+            .post(&url)
+            .header("Authorization", &format!("Bearer {}", self.config.token))
+            .send_json(&body)
+            .map(|_| ())
+    }
+
+    fn publish_camera(&self, camera: &str, last_event: Option<&Event>) {
+        let mut attributes = serde_json::json!({
+            "friendly_name": camera,
+        });
+        if let Some(event) = last_event {
+            attributes["last_event"] = event.kind.name().into();
+            attributes["last_event_time"] = chrono::Local::now().to_rfc3339().into();
+        }
+
+        let body = serde_json::json!({
+            "state": "idle",
+            "attributes": attributes,
+        });
+        if let Err(e) = self.post(&format!("/api/states/{}", entity_id(camera)), body) {
+            eprintln!("Home Assistant : impossible de publier {} : {}", camera, e);
+        }
+    }
+
+    fn fire_event(&self, event: &Event) {
+        let path = format!("/api/events/{}", self.config.event_type);
+        let body = serde_json::json!({
+            "camera": event.camera,
+            "event": event.kind.name(),
+            "entity_id": entity_id(&event.camera),
+        });
+        if let Err(e) = self.post(&path, body) {
+            eprintln!("Home Assistant : impossible d'envoyer l'événement : {}", e);
+        }
+        self.publish_camera(&event.camera, Some(event));
+    }
+
+    fn listen_loop(&self, bus: EventBus) {
+        loop {
+            if let Err(e) = self.listen(&bus) {
+                eprintln!("Home Assistant : connexion WebSocket perdue : {}", e);
+            }
+            thread::sleep(Duration::from_secs(10));
+        }
+    }
+
+    fn listen(&self, bus: &EventBus) -> Result<(), String> {
+        let url = format!(
+            "{}/api/websocket",
+            self.config
+                .url
+                .trim_end_matches('/')
+                .replacen("http", "ws", 1)
+        );
+        let (mut socket, _) = tungstenite::connect(url).map_err(|e| e.to_string())?;
+
+        let auth = serde_json::json!({ "type": "auth", "access_token": self.config.token });
+        socket
+            .send(Message::text(auth.to_string()))
+            .map_err(|e| e.to_string())?;
+
+        loop {
+            let message = socket.read().map_err(|e| e.to_string())?;
+            let Message::Text(text) = message else {
+                continue;
+            };
+            let Ok(value) = serde_json::from_str::<serde_json::Value>(text.as_str()) else {
+                continue;
+            };
+
+            match value["type"].as_str() {
+                Some("auth_ok") => {
+                    let subscribe = serde_json::json!({
+                        "id": 1,
+                        "type": "subscribe_events",
+                        "event_type": self.config.command_event,
+                    });
+                    socket
+                        .send(Message::text(subscribe.to_string()))
+                        .map_err(|e| e.to_string())?;
+                }
+                Some("auth_invalid") => return Err("jeton refusé".to_string()),
+                Some("event") => {
+                    let Ok(data) =
+                        serde_json::from_value::<CommandData>(value["event"]["data"].clone())
+                    else {
+                        continue;
+                    };
+
+                    let command = match (data.command.as_str(), data.camera) {
+                        ("switch_camera", Some(camera)) => RemoteCommand::SwitchCamera(camera),
+                        ("snapshot", _) => RemoteCommand::Snapshot,
+                        ("dnd_on", _) => RemoteCommand::DoNotDisturb(true),
+                        ("dnd_off", _) => RemoteCommand::DoNotDisturb(false),
+                        (other, _) => {
+                            eprintln!("Home Assistant : commande inconnue {}", other);
+                            continue;
+                        }
+                    };
+                    bus.publish(CameraEvent::Command(command));
+                }
+                _ => {}
+            }
+        }
+    }
+}
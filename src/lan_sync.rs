@@ -0,0 +1,256 @@
+use crate::bus::{CameraEvent, EventBus};
+use crate::event::RemoteCommand;
+use crate::net;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::Arc;
+use std::thread;
+
+const MAX_PACKET_LEN: usize = 1024;
+const MAC_LEN: usize = 32;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LanSyncConfig {
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub peers: Vec<String>,
+    #[serde(default = "default_follow_camera")]
+    pub follow_camera: bool,
+    pub secret: Option<String>,
+}
+
+fn default_port() -> u16 {
+    47_210
+}
+
+fn default_follow_camera() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SyncMessage {
+    Camera { camera: String },
+    Doorbell { camera: String },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Packet {
+    instance: u64,
+    #[serde(flatten)]
+    message: SyncMessage,
+}
+
+fn signer(secret: &str) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("clé HMAC invalide")
+}
+
+fn seal(secret: Option<&str>, mut data: Vec<u8>) -> Vec<u8> {
+    if let Some(secret) = secret {
+        let mut mac = signer(secret);
+        mac.update(&data);
+        data.extend_from_slice(&mac.finalize().into_bytes());
+    }
+    data
+}
+
+fn open<'a>(secret: Option<&str>, datagram: &'a [u8]) -> Option<&'a [u8]> {
+    let Some(secret) = secret else {
+        return Some(datagram);
+    };
+    let (data, tag) = datagram.split_at(datagram.len().checked_sub(MAC_LEN)?);
+    let mut mac = signer(secret);
+    mac.update(data);
+    mac.verify_slice(tag).ok().map(|()| data)
+}
+
+fn peer_address(peer: &str, port: u16) -> String {
+    match peer.parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, port).to_string(),
+        Err(_) if peer.contains(':') => peer.to_string(),
+        Err(_) => format!("{}:{}", net::http_host(peer), port),
+    }
+}
+
+fn peer_ips(config: &LanSyncConfig) -> Vec<IpAddr> {
+    config
+        .peers
+        .iter()
+        .flat_map(|peer| {
+            let address = peer_address(peer, config.port);
+            match address.to_socket_addrs() {
+                Ok(addresses) => addresses
+                    .map(|address| address.ip().to_canonical())
+                    .collect(),
+                Err(e) => {
+                    eprintln!("Pair de synchronisation {} introuvable : {}", peer, e);
+                    Vec::new()
+                }
+            }
+        })
+        .collect()
+}
+
+fn is_allowed_peer(allowed: &[IpAddr], from: IpAddr) -> bool {
+    allowed.is_empty() || allowed.contains(&from.to_canonical())
+}
+
+#[derive(Clone)]
+pub struct LanSync {
+    config: LanSyncConfig,
+    socket: Arc<UdpSocket>,
+    instance: u64,
+}
+
+impl LanSync {
+    pub fn spawn(config: LanSyncConfig, bus: EventBus) -> Option<Self> {
+        let socket = match UdpSocket::bind(("0.0.0.0", config.port))
+            .and_then(|socket| socket.set_broadcast(true).map(|()| socket))
+        {
+            Ok(socket) => Arc::new(socket),
+            Err(e) => {
+                eprintln!(
+                    "Impossible d'ouvrir le port de synchronisation {} : {}",
+                    config.port, e
+                );
+                return None;
+            }
+        };
+        println!("Synchronisation LAN active sur le port {}", config.port);
+
+        let instance = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default()
+            ^ u64::from(std::process::id());
+
+        let receiver = socket.clone();
+        let follow_camera = config.follow_camera;
+        let secret = config.secret.clone();
+        let allowed = peer_ips(&config);
+        thread::spawn(move || {
+            let mut buffer = [0u8; MAX_PACKET_LEN];
+            loop {
+                let (len, from) = match receiver.recv_from(&mut buffer) {
+                    Ok(received) => received,
+                    Err(e) => {
+                        eprintln!("Erreur de réception de synchronisation : {}", e);
+                        continue;
+                    }
+                };
+                if !is_allowed_peer(&allowed, from.ip()) {
+                    eprintln!(
+                        "Message de synchronisation ignoré de {} : pair inconnu",
+                        from
+                    );
+                    continue;
+                }
+                let Some(data) = open(secret.as_deref(), &buffer[..len]) else {
+                    eprintln!("Message de synchronisation non authentifié de {}", from);
+                    continue;
+                };
+                let packet: Packet = match serde_json::from_slice(data) {
+                    Ok(packet) => packet,
+                    Err(e) => {
+                        eprintln!("Message de synchronisation invalide de {} : {}", from, e);
+                        continue;
+                    }
+                };
+                if packet.instance == instance {
+                    continue;
+                }
+
+                let command = match packet.message {
+                    SyncMessage::Camera { camera } if follow_camera => {
+                        RemoteCommand::PeerCamera(camera)
+                    }
+                    SyncMessage::Camera { .. } => continue,
+                    SyncMessage::Doorbell { camera } => RemoteCommand::PeerDoorbell(camera),
+                };
+                bus.publish(CameraEvent::Command(command));
+            }
+        });
+
+        Some(LanSync {
+            config,
+            socket,
+            instance,
+        })
+    }
+
+    fn send(&self, message: SyncMessage) {
+        let packet = Packet {
+            instance: self.instance,
+            message,
+        };
+        let data = match serde_json::to_vec(&packet) {
+            Ok(data) => seal(self.config.secret.as_deref(), data),
+            Err(e) => {
+                eprintln!("Impossible de sérialiser la synchronisation : {}", e);
+                return;
+            }
+        };
+
+        let broadcast = [format!("255.255.255.255:{}", self.config.port)];
+        let peers = if self.config.peers.is_empty() {
+            &broadcast[..]
+        } else {
+            &self.config.peers[..]
+        };
+        for peer in peers {
+            let peer = peer_address(peer, self.config.port);
+            if let Err(e) = self.socket.send_to(&data, &peer) {
+                eprintln!("Impossible de synchroniser avec {} : {}", peer, e);
+            }
+        }
+    }
+
+    pub fn announce_camera(&self, camera: &str) {
+        if self.config.follow_camera {
+            self.send(SyncMessage::Camera {
+                camera: camera.to_string(),
+            });
+        }
+    }
+
+    pub fn announce_doorbell(&self, camera: &str) {
+        self.send(SyncMessage::Doorbell {
+            camera: camera.to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_packets_signed_with_the_shared_secret_are_accepted() {
+        let data = br#"{"instance":1,"type":"doorbell","camera":"Portail"}"#.to_vec();
+
+        let sealed = seal(Some("secret"), data.clone());
+        assert_eq!(open(Some("secret"), &sealed), Some(&data[..]));
+        assert_eq!(open(Some("autre"), &sealed), None);
+        assert_eq!(open(Some("secret"), &data), None);
+
+        let mut tampered = sealed.clone();
+        tampered[30] ^= 1;
+        assert_eq!(open(Some("secret"), &tampered), None);
+        assert_eq!(open(None, &data), Some(&data[..]));
+    }
+
+    #[test]
+    fn packets_from_unlisted_hosts_are_dropped_when_peers_are_set() {
+        let peer: IpAddr = "192.168.1.21".parse().unwrap();
+        let stranger: IpAddr = "192.168.1.66".parse().unwrap();
+        let mapped: IpAddr = "::ffff:192.168.1.21".parse().unwrap();
+
+        assert!(is_allowed_peer(&[], stranger));
+        assert!(is_allowed_peer(&[peer], peer));
+        assert!(is_allowed_peer(&[peer], mapped));
+        assert!(!is_allowed_peer(&[peer], stranger));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::decoder::VideoFrame;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const WINDOW: usize = 120;
+const LOG_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencySummary {
+    pub decode: Duration,
+    pub display: Duration,
+    pub max_total: Duration,
+}
+
+pub struct LatencyMeter {
+    samples: VecDeque<(Duration, Duration)>,
+    last_log: Instant,
+}
+
+impl Default for LatencyMeter {
+    fn default() -> Self {
+        LatencyMeter {
+            samples: VecDeque::with_capacity(WINDOW),
+            last_log: Instant::now(),
+        }
+    }
+}
+
+impl LatencyMeter {
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    fn record_sample(&mut self, decode: Duration, display: Duration) {
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((decode, display));
+    }
+
+    pub fn record(&mut self, frame: &VideoFrame, displayed_at: Instant) {
+        self.record_sample(
+            frame
+                .decoded_at
+                .saturating_duration_since(frame.received_at),
+            displayed_at.saturating_duration_since(frame.decoded_at),
+        );
+
+        if self.last_log.elapsed() >= LOG_INTERVAL
+            && let Some(summary) = self.summary()
+        {
+            self.last_log = Instant::now();
+            println!(
+                "Latence {} : réception→décodage {} ms, décodage→affichage {} ms (max {} ms)",
+                frame.camera,
+                summary.decode.as_millis(),
+                summary.display.as_millis(),
+                summary.max_total.as_millis()
+            );
+        }
+    }
+
+    pub fn summary(&self) -> Option<LatencySummary> {
+        let count = self.samples.len() as u32;
+        if count == 0 {
+            return None;
+        }
+        Some(LatencySummary {
+            decode: self
+                .samples
+                .iter()
+                .map(|(decode, _)| *decode)
+                .sum::<Duration>()
+                / count,
+            display: self
+                .samples
+                .iter()
+                .map(|(_, display)| *display)
+                .sum::<Duration>()
+                / count,
+            max_total: self
+                .samples
+                .iter()
+                .map(|(decode, display)| *decode + *display)
+                .max()
+                .unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_over_sliding_window() {
+        let mut meter = LatencyMeter::default();
+        assert_eq!(meter.summary(), None);
+
+        let ms = Duration::from_millis;
+        meter.record_sample(ms(100), ms(100));
+        for _ in 0..WINDOW {
+            meter.record_sample(ms(10), ms(20));
+        }
+        assert_eq!(
+            meter.summary(),
+            Some(LatencySummary {
+                decode: ms(10),
+                display: ms(20),
+                max_total: ms(30),
+            })
+        );
+    }
+}
@@ -1,38 +1,243 @@
+mod automation;
+mod backup;
+mod brightness;
+mod bus;
+mod call;
+mod chime;
+mod color;
+mod dashboard;
+mod daynight;
+mod decoder;
+mod display;
+mod dnd;
+mod doorbell;
+mod email;
+mod event;
+mod export;
+mod frame_store;
+mod health;
+mod homeassistant;
+mod import;
+mod lan_sync;
+mod latency;
+mod media_index;
+mod net;
+mod onvif;
+mod pacing;
+mod playback;
+mod power;
+mod presence;
+mod probe;
+mod ptz;
+mod recorder;
+mod remote;
+mod reolink;
+mod restream;
+mod simulator;
+mod state;
+mod stats;
+mod storage;
+mod supervisor;
+mod system;
+#[cfg(test)]
+mod testing;
+mod trash;
+mod tts;
+mod web;
+
+use automation::{Automation, RuleConfig};
+use brightness::{Brightness, BrightnessConfig};
+use bus::{CameraEvent, EventBus};
+use call::{Call, CallConfig};
+use chime::{Chime, ChimeConfig};
+use color::ColorConfig;
 use crossbeam_channel::{Receiver, unbounded};
+use dashboard::Dashboard;
+use daynight::DayNightConfig;
+use decoder::{
+    DropPolicy, LatencyMode, LoudNoiseConfig, NetworkQuality, RetryPolicy, StreamStatus,
+    VideoFrame, VideoStream, run_decoder_managed,
+};
+use display::{ControlsPosition, DisplayMode, Rotation, Zoom, fit_rect};
+use dnd::{DndConfig, DoNotDisturb};
+use doorbell::{DoorbellConfig, DoorbellHealth, DoorbellMonitor, PollRate, WakeAction};
 use eframe::egui::RichText;
 use eframe::egui::{self, ahash::HashMap};
-use ffmpeg_next::Dictionary;
-use ffmpeg_next::{self as ffmpeg};
+use email::{EmailConfig, EmailNotifier};
+use event::{Event, EventKind, RemoteCommand};
+use export::PrivacyMask;
+use frame_store::FrameStore;
+use health::{HealthMonitor, HealthReportConfig};
+use homeassistant::{HomeAssistant, HomeAssistantConfig};
+use lan_sync::{LanSync, LanSyncConfig};
+use latency::LatencyMeter;
+use media_index::{MediaFilter, MediaIndex, MediaKind, MediaRecord};
+use onvif::AnalyticsObject;
+use pacing::FrameBudget;
+use playback::SyncPlayback;
+use power::PowerConfig;
+use presence::{Occupancy, PresenceConfig};
+use ptz::{PatrolConfig, PtzPreset};
+use recorder::RecordingSource;
+use reolink::{ReolinkClient, ReolinkConfig};
 use serde::Deserialize;
+use simulator::DoorbellSimulator;
+use state::AppState;
+use stats::ActivityStats;
+use std::sync::Arc;
 use std::thread;
+use storage::{SharedMediaStore, StorageConfig};
+use system::{SystemMonitor, SystemStatusConfig};
+use trash::{Trash, TrashConfig};
+use tts::{Announcer, TtsConfig};
+use web::WebConfig;
+
+pub const WIDTH: u32 = 1280;
+pub const HEIGHT: u32 = 720;
+const CONFIG_PATH: &str = "config.toml";
+const STATE_PATH: &str = "state.toml";
+const ANALYTICS_TTL: std::time::Duration = std::time::Duration::from_secs(2);
+const PEER_RING_SUPPRESSION: std::time::Duration = std::time::Duration::from_secs(10);
+const DEFAULT_FPS: f64 = 25.0;
+const IDLE_REPAINT: std::time::Duration = std::time::Duration::from_millis(250);
+const ASLEEP_REPAINT: std::time::Duration = std::time::Duration::from_secs(1);
+
+struct Toast {
+    message: String,
+    is_error: bool,
+    undo: bool,
+    shown: std::time::Instant,
+}
+
+impl Toast {
+    fn success(message: String) -> Self {
+        Toast {
+            message,
+            is_error: false,
+            undo: false,
+            shown: std::time::Instant::now(),
+        }
+    }
 
-const WIDTH: u32 = 1280;
-const HEIGHT: u32 = 720;
+    fn undoable(message: String) -> Self {
+        Toast {
+            message,
+            is_error: false,
+            undo: true,
+            shown: std::time::Instant::now(),
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Toast {
+            message,
+            is_error: true,
+            undo: false,
+            shown: std::time::Instant::now(),
+        }
+    }
+
+    fn duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(if self.is_error || self.undo { 8 } else { 3 })
+    }
+
+    fn show(&self, ctx: &egui::Context, controls: ControlsPosition) -> bool {
+        let mut undo_clicked = false;
+        let (icon, color) = if self.is_error {
+            ("⚠", egui::Color32::from_rgb(230, 60, 60))
+        } else {
+            ("✔", egui::Color32::from_rgb(60, 200, 90))
+        };
+        egui::Area::new("toast".into())
+            .anchor(
+                egui::Align2::CENTER_BOTTOM,
+                egui::vec2(0.0, -controls.clearance().1 - 10.0),
+            )
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::new()
+                    .fill(egui::Color32::from_black_alpha(220))
+                    .inner_margin(16.0)
+                    .corner_radius(15.0)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                RichText::new(format!("{} {}", icon, self.message))
+                                    .color(color)
+                                    .size(26.),
+                            );
+                            if self.undo {
+                                undo_clicked =
+                                    ui.button(RichText::new("↶ Annuler").size(26.)).clicked();
+                            }
+                        });
+                    });
+            });
+        undo_clicked
+    }
+}
 
 struct VideoApp {
     config: RootConfig,
     current_url: String,
     running_sender: HashMap<String, crossbeam_channel::Sender<bool>>,
-    packet_receiver: Receiver<VideoFrame>,
+    bus: EventBus,
+    bus_receiver: Receiver<CameraEvent>,
+    stream_status: HashMap<String, StreamStatus>,
+    analytics: HashMap<String, (std::time::Instant, Vec<AnalyticsObject>)>,
     texture: Option<egui::TextureHandle>,
+    frame_store: FrameStore,
+    frame_budget: FrameBudget,
+    dropped_frames: u64,
+    latency: LatencyMeter,
+    current_frame: Option<Arc<VideoFrame>>,
     notification_timer: Option<std::time::Instant>,
+    capture_view: bool,
+    view_rect: Option<egui::Rect>,
+    low_power_since: Option<std::time::Instant>,
+    repaint: Option<egui::Context>,
+    toast: Option<Toast>,
     show_gallery: bool,
-    gallery_images: Vec<std::path::PathBuf>,
+    gallery_items: Vec<MediaRecord>,
+    gallery_filter: MediaFilter,
     gallery_index: usize,
     gallery_texture: Option<egui::TextureHandle>,
+    gallery_zoom: Zoom,
+    gallery_selection: std::collections::HashSet<std::path::PathBuf>,
+    trash: Trash,
+    deleted: Vec<(MediaRecord, std::path::PathBuf)>,
+    gallery_compare: Option<MediaRecord>,
+    compare_texture: Option<egui::TextureHandle>,
+    compare_blend: Option<f32>,
+    playback: Option<SyncPlayback>,
     last_activity: std::time::Instant,
-}
-
-struct VideoStream {
-    url: String,
-    packet_sender: crossbeam_channel::Sender<VideoFrame>,
-    stop_receiver: Receiver<bool>,
-    running: bool,
-}
-
-struct VideoFrame {
-    data: Vec<u8>,
-    url: String,
+    chime: Option<Chime>,
+    announcer: Option<Announcer>,
+    email_notifier: Option<EmailNotifier>,
+    pending_email: Option<(Event, std::time::Instant)>,
+    media_index: MediaIndex,
+    media_store: SharedMediaStore,
+    show_stats: bool,
+    show_dashboard: bool,
+    dashboard: Dashboard,
+    stats: Option<ActivityStats>,
+    stats_camera: usize,
+    saved_state: AppState,
+    is_night: Option<bool>,
+    brightness: Option<Brightness>,
+    show_brightness: bool,
+    spotlight_on: std::collections::HashSet<String>,
+    powered_off: std::collections::HashSet<String>,
+    health: Option<HealthMonitor>,
+    system_monitor: SystemMonitor,
+    doorbell_health: Option<DoorbellHealth>,
+    doorbell_simulator: Option<DoorbellSimulator>,
+    doorbell_poll_rate: Option<PollRate>,
+    lan_sync: Option<LanSync>,
+    last_ring: Option<std::time::Instant>,
+    call: Option<Call>,
+    dnd: DoNotDisturb,
+    occupancy: Option<Occupancy>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -41,18 +246,100 @@ struct Config {
     capture_path: String,
     cursor_visible: bool,
     use_tcp_for_rtsp: bool,
+    #[serde(default = "default_drop_late_frames")]
+    drop_late_frames: bool,
+    #[serde(default)]
+    frame_drop_policy: DropPolicy,
+    #[serde(default = "default_export_path")]
+    export_path: String,
+    bind_address: Option<std::net::IpAddr>,
+    #[serde(default = "storage::default_filename_template")]
+    filename_template: String,
+    #[serde(default)]
+    controls: ControlsPosition,
+    #[serde(default)]
+    rotation: Rotation,
+}
+
+impl Config {
+    fn controls_on_screen(&self) -> ControlsPosition {
+        self.controls.on_screen(self.rotation)
+    }
+}
+
+fn default_drop_late_frames() -> bool {
+    true
+}
+
+fn default_record_audio() -> bool {
+    true
+}
+
+fn default_export_path() -> String {
+    "exports".to_string()
 }
 
 #[derive(Deserialize, Debug)]
 struct Camera {
     name: String,
+    #[serde(default)]
     url: String,
+    remote: Option<String>,
+    remote_camera: Option<String>,
+    remote_token: Option<String>,
+    #[serde(default)]
+    latency: LatencyMode,
+    loud_noise: Option<LoudNoiseConfig>,
+    substream_url: Option<String>,
+    restream_url: Option<String>,
+    #[serde(default)]
+    ffmpeg_options: std::collections::BTreeMap<String, String>,
+    #[serde(default)]
+    retry: RetryPolicy,
+    #[serde(default, rename = "loop")]
+    looping: bool,
+    #[serde(default)]
+    color: ColorConfig,
+    #[serde(default)]
+    display: DisplayMode,
+    #[serde(default)]
+    privacy_masks: Vec<PrivacyMask>,
+    #[serde(default = "default_record_audio")]
+    record_audio: bool,
+    #[serde(default)]
+    onvif_metadata: bool,
+    reolink: Option<ReolinkConfig>,
+    #[serde(default)]
+    ptz_presets: Vec<PtzPreset>,
+    patrol: Option<PatrolConfig>,
+    power: Option<PowerConfig>,
 }
 
 #[derive(Deserialize, Debug)]
 struct RootConfig {
     config: Config,
     camera: Vec<Camera>,
+    doorbell: Option<DoorbellConfig>,
+    chime: Option<ChimeConfig>,
+    call: Option<CallConfig>,
+    dnd: Option<DndConfig>,
+    presence: Option<PresenceConfig>,
+    tts: Option<TtsConfig>,
+    email: Option<EmailConfig>,
+    daynight: Option<DayNightConfig>,
+    brightness: Option<BrightnessConfig>,
+    #[serde(default)]
+    rule: Vec<RuleConfig>,
+    home_assistant: Option<HomeAssistantConfig>,
+    web: Option<WebConfig>,
+    sync: Option<LanSyncConfig>,
+    health_report: Option<HealthReportConfig>,
+    #[serde(default)]
+    system_status: SystemStatusConfig,
+    #[serde(default)]
+    storage: StorageConfig,
+    #[serde(default)]
+    trash: TrashConfig,
 }
 
 impl RootConfig {
@@ -67,20 +354,179 @@ impl RootConfig {
     fn get_first_camera_url(&self) -> Option<String> {
         self.camera.first().map(|cam| cam.url.clone())
     }
+
+    fn get_camera_name_by_url(&self, url: &str) -> Option<String> {
+        self.camera
+            .iter()
+            .find(|cam| cam.url == url)
+            .map(|cam| cam.name.clone())
+    }
+
+    fn restream_urls_by_name(&self) -> std::collections::HashMap<String, String> {
+        self.camera
+            .iter()
+            .filter_map(|cam| Some((cam.name.clone(), cam.restream_url.clone()?)))
+            .collect()
+    }
+
+    fn resolve_remote_cameras(&mut self) {
+        self.camera.retain_mut(|camera| {
+            let Some(remote) = &camera.remote else {
+                return true;
+            };
+            let remote_camera = camera.remote_camera.as_ref().unwrap_or(&camera.name);
+            let token = camera.remote_token.as_deref();
+            match remote::resolve(remote, remote_camera, token) {
+                Ok(url) => {
+                    println!("Caméra {} reçue de {} : {}", camera.name, remote, url);
+                    camera.url = url;
+                    true
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Impossible de récupérer la caméra {} depuis {} : {}",
+                        camera.name, remote, e
+                    );
+                    if camera.url.is_empty() {
+                        eprintln!(
+                            "Caméra {} ignorée : aucun flux vidéo disponible",
+                            camera.name
+                        );
+                        return false;
+                    }
+                    true
+                }
+            }
+        });
+    }
+
+    fn camera_urls_by_name(&self) -> std::collections::HashMap<String, String> {
+        self.camera
+            .iter()
+            .map(|cam| (cam.name.clone(), cam.url.clone()))
+            .collect()
+    }
+
+    fn recording_sources(&self) -> std::collections::HashMap<String, RecordingSource> {
+        self.camera
+            .iter()
+            .map(|cam| {
+                let source = RecordingSource {
+                    url: cam.url.clone(),
+                    audio: cam.record_audio,
+                };
+                (cam.name.clone(), source)
+            })
+            .collect()
+    }
+
+    fn reolink_clients(&self) -> std::collections::HashMap<String, ReolinkClient> {
+        self.camera
+            .iter()
+            .filter_map(|cam| {
+                let reolink = cam.reolink.clone()?;
+                Some((cam.name.clone(), ReolinkClient::new(reolink)))
+            })
+            .collect()
+    }
+
+    fn display_mode(&self, name: &str) -> DisplayMode {
+        self.camera
+            .iter()
+            .find(|cam| cam.name == name)
+            .map(|cam| cam.display)
+            .unwrap_or_default()
+    }
+
+    fn privacy_masks(&self, name: &str) -> Vec<PrivacyMask> {
+        self.camera
+            .iter()
+            .find(|cam| cam.name == name)
+            .map(|cam| cam.privacy_masks.clone())
+            .unwrap_or_default()
+    }
+
+    fn get_camera_url_by_name(&self, name: &str) -> Option<String> {
+        self.camera
+            .iter()
+            .find(|cam| cam.name == name)
+            .map(|cam| cam.url.clone())
+    }
 }
 
 impl VideoApp {
+    fn spawn_decoders(&mut self) {
+        for camera in self.config.camera.iter() {
+            let (stop_sender, stop_receiver) = unbounded::<bool>();
+            let video_stream = VideoStream {
+                url: camera.url.clone(),
+                bus: self.bus.clone(),
+                stop_receiver,
+                running: camera.url == self.current_url && !self.powered_off.contains(&camera.name),
+                latency: camera.latency,
+                camera_name: camera.name.clone(),
+                loud_noise: camera.loud_noise.clone(),
+                substream_url: camera.substream_url.clone(),
+                restream_url: camera.restream_url.clone(),
+                ffmpeg_options: camera.ffmpeg_options.clone(),
+                retry: camera.retry.clone(),
+                looping: camera.looping,
+                drop_policy: self.config.config.frame_drop_policy,
+                frame_budget: self.frame_budget.clone(),
+                color: camera.color,
+                onvif_metadata: camera.onvif_metadata,
+                repaint: self.repaint.clone(),
+            };
+            let has_to_wait_for_keyframe = self.config.config.has_to_wait_for_keyframe;
+            let use_tcp_for_rtsp = self.config.config.use_tcp_for_rtsp;
+
+            thread::spawn(move || {
+                run_decoder_managed(video_stream, has_to_wait_for_keyframe, use_tcp_for_rtsp);
+            });
+
+            self.running_sender.insert(camera.url.clone(), stop_sender);
+        }
+    }
+
+    fn restart_streams(&mut self) {
+        println!("Redémarrage des flux...");
+        self.running_sender.clear();
+        self.stream_status.clear();
+        self.texture = None;
+        self.current_frame = None;
+        self.frame_store.clear();
+        self.latency.reset();
+        self.spawn_decoders();
+        self.last_activity = std::time::Instant::now();
+    }
+
     fn switch_stream(&mut self, new_url: &str) {
+        if new_url != self.current_url
+            && let Some(lan_sync) = &self.lan_sync
+            && let Some(name) = self.config.get_camera_name_by_url(new_url)
+        {
+            lan_sync.announce_camera(&name);
+        }
+        self.show_stream(new_url);
+    }
+
+    fn show_stream(&mut self, new_url: &str) {
         if let Some(sender) = self.running_sender.get(&self.current_url) {
             let _ = sender.send(false);
         }
 
-        if let Some(sender) = self.running_sender.get(new_url) {
+        if let Some(sender) = self.running_sender.get(new_url)
+            && !self.is_powered_off(new_url)
+        {
             let _ = sender.send(true);
         }
 
         self.current_url = new_url.to_string();
         self.texture = None;
+        self.current_frame = None;
+        self.frame_store.set_active(new_url);
+        self.dropped_frames = 0;
+        self.latency.reset();
     }
 
     fn next_camera(&mut self) {
@@ -110,8 +556,22 @@ impl VideoApp {
     }
 
     fn take_snapshot(&self, frame: &VideoFrame) {
-        let data = frame.data.clone();
-        let capture_path = self.config.config.capture_path.clone();
+        self.save_capture(frame.data.clone(), [WIDTH, HEIGHT], false);
+    }
+
+    fn save_view_capture(&self, image: &egui::ColorImage, pixels_per_point: f32) {
+        let image = match self.view_rect {
+            Some(rect) => image.region(&rect, Some(pixels_per_point)),
+            None => image.clone(),
+        };
+        let size = [image.size[0] as u32, image.size[1] as u32];
+        self.save_capture(image.as_raw().to_vec(), size, true);
+    }
+
+    fn save_capture(&self, data: Vec<u8>, [width, height]: [u32; 2], view: bool) {
+        let media_store = self.media_store.clone();
+        let media_index = self.media_index.clone();
+        let bus = self.bus.clone();
         let current_url = self.current_url.clone();
 
         let num = self
@@ -123,143 +583,1244 @@ impl VideoApp {
         let raw_cam_name = self.config.get_camera_names()[num].clone();
 
         thread::spawn(move || {
-            let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+            let now = chrono::Local::now();
+            let mut filename = media_store.file_name(&raw_cam_name, None, now, "png");
+            if view {
+                filename = format!("{}_vue.png", filename.trim_end_matches(".png"));
+            }
+
+            let result = image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(width, height, data)
+                .ok_or_else(|| "échec de la création du buffer d'image".to_string())
+                .and_then(|img_buffer| {
+                    media_store.write(&filename, |path| {
+                        img_buffer.save(path).map_err(|e| e.to_string())
+                    })
+                });
+
+            match result {
+                Ok(path) => {
+                    media_index.record_media(
+                        now.naive_local(),
+                        &raw_cam_name,
+                        MediaKind::Snapshot,
+                        &path,
+                        None,
+                    );
+                    bus.publish(CameraEvent::SnapshotSaved {
+                        camera: raw_cam_name,
+                        path,
+                    });
+                }
+                Err(e) => {
+                    eprintln!("Erreur lors de la sauvegarde de l'image : {}", e);
+                    bus.publish(CameraEvent::SnapshotFailed {
+                        camera: raw_cam_name,
+                        error: e,
+                    });
+                }
+            }
+        });
+    }
+
+    fn open_gallery(&mut self) {
+        self.trash.purge();
+        self.media_index.remove_missing_media();
+        self.apply_gallery_filter();
+        self.show_gallery = true;
+    }
 
-            let cam_name = raw_cam_name
-                .replace("://", "_")
-                .replace("/", "_")
-                .replace(".", "_");
+    fn apply_gallery_filter(&mut self) {
+        self.gallery_items = self.media_index.media(&self.gallery_filter);
+        let items = &self.gallery_items;
+        self.gallery_selection
+            .retain(|path| items.iter().any(|item| &item.path == path));
+        self.gallery_index = 0;
+        self.gallery_texture = None;
+        self.stop_compare();
+    }
 
-            let filename = format!("{}/{}_{}.png", capture_path, timestamp, cam_name);
+    fn show_gallery_filters(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut filter = self.gallery_filter.clone();
 
-            if let Some(img_buffer) =
-                image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(1280, 720, data)
-            {
-                if let Err(e) = img_buffer.save(&filename) {
-                    eprintln!("Erreur lors de la sauvegarde de l'image : {}", e);
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 16.0;
+            ui.style_mut().override_font_id = Some(egui::FontId::proportional(24.0));
+
+            ui.selectable_value(&mut filter.camera, None, "Toutes");
+            for name in self.config.get_camera_names() {
+                ui.selectable_value(&mut filter.camera, Some(name.clone()), name);
+            }
+            ui.separator();
+
+            date_filter(ui, "Du", "filter_from", &mut filter.from);
+            date_filter(ui, "Au", "filter_to", &mut filter.to);
+
+            let event_label = |event: Option<EventKind>| match event {
+                None => "Tous les évènements",
+                Some(EventKind::Visitor) => "Visites",
+                Some(EventKind::Person) => "Personnes",
+                Some(EventKind::Motion) => "Mouvements",
+                Some(EventKind::LoudNoise) => "Bruits forts",
+            };
+            egui::ComboBox::from_id_salt("filter_event")
+                .selected_text(event_label(filter.event))
+                .show_ui(ui, |ui| {
+                    for event in [
+                        None,
+                        Some(EventKind::Visitor),
+                        Some(EventKind::Person),
+                        Some(EventKind::Motion),
+                        Some(EventKind::LoudNoise),
+                    ] {
+                        ui.selectable_value(&mut filter.event, event, event_label(event));
+                    }
+                });
+
+            if ui.button("Réinitialiser").clicked() {
+                filter = MediaFilter::default();
+            }
+
+            ui.label(format!("{} images", self.gallery_items.len()));
+        });
+
+        let changed = filter != self.gallery_filter;
+        self.gallery_filter = filter;
+        changed
+    }
+
+    fn show_gallery_days(&mut self, ui: &mut egui::Ui) {
+        let today = chrono::Local::now().date_naive();
+        let selected_day = self
+            .gallery_items
+            .get(self.gallery_index)
+            .map(|item| item.timestamp.date());
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (day, indices) in media_index::group_by_day(&self.gallery_items) {
+                let title = match (today - day).num_days() {
+                    0 => "Aujourd'hui".to_string(),
+                    1 => "Hier".to_string(),
+                    _ => day.format("%d/%m/%Y").to_string(),
+                };
+                egui::CollapsingHeader::new(
+                    RichText::new(format!("{} ({})", title, indices.len())).size(22.),
+                )
+                .id_salt(day)
+                .default_open(Some(day) == selected_day)
+                .show(ui, |ui| {
+                    for index in indices {
+                        let item = &self.gallery_items[index];
+                        let selected = self.gallery_selection.contains(&item.path);
+                        let icon = match (selected, item.kind) {
+                            (true, _) => "☑",
+                            (false, MediaKind::Snapshot) => "📷",
+                            (false, MediaKind::Clip) => "🎞",
+                        };
+                        let label = format!(
+                            "{} {} · {}",
+                            icon,
+                            item.timestamp.format("%H:%M:%S"),
+                            item.camera
+                        );
+                        let response = ui.selectable_label(
+                            selected || index == self.gallery_index,
+                            RichText::new(label).size(20.),
+                        );
+
+                        let toggle = response.long_touched()
+                            || response.secondary_clicked()
+                            || (response.clicked() && !self.gallery_selection.is_empty());
+                        if toggle {
+                            let path = item.path.clone();
+                            if !self.gallery_selection.remove(&path) {
+                                self.gallery_selection.insert(path);
+                            }
+                        } else if response.clicked() {
+                            self.gallery_index = index;
+                            self.gallery_texture = None;
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    fn load_gallery_texture(&mut self, ctx: &egui::Context) {
+        if self.gallery_items.is_empty() {
+            self.gallery_texture = None;
+            return;
+        }
+
+        if let Some(item) = self.gallery_items.get(self.gallery_index) {
+            self.gallery_texture = load_media_texture(ctx, &item.path);
+            self.gallery_zoom = Zoom::default();
+        }
+    }
+
+    fn start_compare(&mut self) {
+        if let Some(item) = self.gallery_items.get(self.gallery_index)
+            && item.kind == MediaKind::Snapshot
+        {
+            self.gallery_compare = Some(item.clone());
+            self.compare_texture = None;
+            self.gallery_next();
+        }
+    }
+
+    fn stop_compare(&mut self) {
+        self.gallery_compare = None;
+        self.compare_texture = None;
+        self.compare_blend = None;
+    }
+
+    fn show_compare_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 16.0;
+            ui.style_mut().override_font_id = Some(egui::FontId::proportional(24.0));
+
+            if self.playback.is_some() {
+                if ui.button("Quitter la lecture").clicked() {
+                    self.playback = None;
+                }
+                return;
+            }
+
+            if !self.gallery_selection.is_empty() {
+                ui.label(format!("{} sélectionné(s)", self.gallery_selection.len()));
+                if ui.button("📤 Exporter").clicked() {
+                    self.export_media(self.selected_media(), false);
+                }
+                if self.config.email.is_some() && ui.button("✉ Envoyer").clicked() {
+                    self.export_media(self.selected_media(), true);
+                }
+                if ui.button("🗑 Supprimer").clicked() {
+                    let items = self.selected_media();
+                    self.gallery_selection.clear();
+                    self.delete_media(items);
+                }
+                if ui.button("✖ Désélectionner").clicked() {
+                    self.gallery_selection.clear();
+                }
+                return;
+            }
+
+            if self.gallery_compare.is_none() {
+                if ui.button("⇆ Comparer").clicked() {
+                    self.start_compare();
+                }
+                if ui.button("▶ Lecture synchronisée").clicked() {
+                    self.open_playback();
+                }
+                if let Some(item) = self.gallery_items.get(self.gallery_index) {
+                    if ui.button("📤 Exporter").clicked() {
+                        self.export_media(vec![item.clone()], false);
+                    }
+                    if self.config.email.is_some() && ui.button("✉ Envoyer").clicked() {
+                        self.export_media(vec![item.clone()], true);
+                    }
+                    if ui.button("🗑 Supprimer").clicked() {
+                        self.delete_media(vec![item.clone()]);
+                    }
+                }
+                return;
+            }
+
+            let mut blend = self.compare_blend.is_some();
+            ui.selectable_value(&mut blend, false, "Côte à côte");
+            ui.selectable_value(&mut blend, true, "Fondu");
+            match (blend, self.compare_blend) {
+                (true, None) => self.compare_blend = Some(0.5),
+                (false, Some(_)) => self.compare_blend = None,
+                _ => {}
+            }
+            if let Some(value) = &mut self.compare_blend {
+                ui.add(egui::Slider::new(value, 0.0..=1.0).show_value(false));
+            }
+
+            if ui.button("Quitter la comparaison").clicked() {
+                self.stop_compare();
+            }
+        });
+    }
+
+    fn show_compare(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let Some(reference) = &self.gallery_compare else {
+            return;
+        };
+        if self.compare_texture.is_none() {
+            self.compare_texture = load_media_texture(ctx, &reference.path);
+        }
+        let (Some(before), Some(after)) = (&self.compare_texture, &self.gallery_texture) else {
+            return;
+        };
+
+        match self.compare_blend {
+            Some(blend) => {
+                let available = ui.available_rect_before_wrap();
+                let rect = fit_rect(available, before.size_vec2());
+                let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                let painter = ui.painter();
+                painter.image(before.id(), rect, uv, egui::Color32::WHITE);
+                painter.image(
+                    after.id(),
+                    rect,
+                    uv,
+                    egui::Color32::from_white_alpha((blend * 255.0) as u8),
+                );
+            }
+            None => {
+                ui.columns(2, |columns| {
+                    for (column, texture) in columns.iter_mut().zip([before, after]) {
+                        let rect =
+                            fit_rect(column.available_rect_before_wrap(), texture.size_vec2());
+                        column.put(
+                            rect,
+                            egui::Image::new(texture).fit_to_exact_size(rect.size()),
+                        );
+                    }
+                });
+            }
+        }
+    }
+
+    fn selected_media(&self) -> Vec<MediaRecord> {
+        self.gallery_items
+            .iter()
+            .filter(|item| self.gallery_selection.contains(&item.path))
+            .cloned()
+            .collect()
+    }
+
+    fn export_media(&self, items: Vec<MediaRecord>, send: bool) {
+        let email = if send {
+            self.config.email.clone()
+        } else {
+            None
+        };
+        let items = items
+            .into_iter()
+            .map(|item| {
+                let masks = self.config.privacy_masks(&item.camera);
+                (item, masks)
+            })
+            .collect();
+        export::spawn(
+            items,
+            std::path::PathBuf::from(&self.config.config.export_path),
+            email,
+        );
+    }
+
+    fn delete_media(&mut self, items: Vec<MediaRecord>) {
+        self.deleted.clear();
+        for item in items {
+            match self.trash.discard(&item.path) {
+                Ok(trashed) => {
+                    self.media_index.remove_media(&item.path);
+                    self.deleted.push((item, trashed));
                 }
+                Err(e) => {
+                    eprintln!("Impossible de supprimer {} : {}", item.path.display(), e);
+                    self.toast = Some(Toast::error(format!("Suppression impossible : {}", e)));
+                }
+            }
+        }
+
+        if !self.deleted.is_empty() {
+            self.toast = Some(Toast::undoable(format!(
+                "{} média(s) mis à la corbeille",
+                self.deleted.len()
+            )));
+        }
+        self.refresh_gallery();
+    }
+
+    fn undo_delete(&mut self) {
+        for (item, trashed) in std::mem::take(&mut self.deleted) {
+            match self.trash.restore(&trashed, &item.path) {
+                Ok(()) => self.media_index.record_media(
+                    item.timestamp,
+                    &item.camera,
+                    item.kind,
+                    &item.path,
+                    item.event,
+                ),
+                Err(e) => eprintln!("Impossible de restaurer {} : {}", item.path.display(), e),
+            }
+        }
+        self.refresh_gallery();
+    }
+
+    fn refresh_gallery(&mut self) {
+        let index = self.gallery_index;
+        self.apply_gallery_filter();
+        self.gallery_index = index.min(self.gallery_items.len().saturating_sub(1));
+    }
+
+    fn open_playback(&mut self) {
+        let filter = MediaFilter {
+            camera: None,
+            event: None,
+            ..self.gallery_filter.clone()
+        };
+        let clips: Vec<MediaRecord> = self
+            .media_index
+            .media(&filter)
+            .into_iter()
+            .filter(|item| item.kind == MediaKind::Clip)
+            .collect();
+        self.stop_compare();
+        self.playback = Some(SyncPlayback::new(&clips));
+    }
+
+    fn gallery_step(&mut self, forward: bool) {
+        let len = self.gallery_items.len();
+        if len == 0 {
+            return;
+        }
+
+        for _ in 0..len {
+            self.gallery_index = if forward {
+                (self.gallery_index + 1) % len
             } else {
-                eprintln!("Échec de la création du buffer d'image");
+                (self.gallery_index + len - 1) % len
+            };
+
+            let item = &self.gallery_items[self.gallery_index];
+            match &self.gallery_compare {
+                Some(reference)
+                    if item.kind != MediaKind::Snapshot
+                        || item.camera != reference.camera
+                        || item.path == reference.path => {}
+                _ => break,
             }
+        }
+        self.gallery_texture = None;
+    }
+
+    fn gallery_next(&mut self) {
+        self.gallery_step(true);
+    }
+
+    fn gallery_previous(&mut self) {
+        self.gallery_step(false);
+    }
+
+    fn close_gallery(&mut self) {
+        self.show_gallery = false;
+        self.gallery_texture = None;
+        self.gallery_selection.clear();
+        self.stop_compare();
+        self.playback = None;
+    }
+
+    fn open_stats(&mut self) {
+        self.close_gallery();
+        self.stats_camera = 0;
+        self.show_stats = true;
+        self.refresh_stats();
+    }
+
+    fn refresh_stats(&mut self) {
+        let records = self.media_index.events();
+        let all = ActivityStats::compute(&records, None);
+        self.stats_camera %= all.cameras.len() + 1;
+
+        self.stats = Some(if self.stats_camera == 0 {
+            all
+        } else {
+            let camera = all.cameras[self.stats_camera - 1].clone();
+            ActivityStats::compute(&records, Some(&camera))
         });
     }
 
-    fn open_gallery(&mut self) {
-        self.gallery_images = match std::fs::read_dir(&self.config.config.capture_path) {
-            Ok(rd) => rd
-                .filter_map(|e| e.ok().map(|d| d.path()))
-                .filter(|p| {
-                    if let Some(ext) = p.extension() {
-                        match ext.to_string_lossy().to_lowercase().as_str() {
-                            "png" | "jpg" | "jpeg" => true,
-                            _ => false,
+    fn stats_title(&self) -> String {
+        match &self.stats {
+            Some(stats) if self.stats_camera > 0 => stats.cameras[self.stats_camera - 1].clone(),
+            _ => "Toutes les caméras".to_string(),
+        }
+    }
+
+    fn stats_next(&mut self) {
+        let count = self.stats.as_ref().map_or(0, |s| s.cameras.len()) + 1;
+        self.stats_camera = (self.stats_camera + 1) % count;
+        self.refresh_stats();
+    }
+
+    fn stats_previous(&mut self) {
+        let count = self.stats.as_ref().map_or(0, |s| s.cameras.len()) + 1;
+        self.stats_camera = (self.stats_camera + count - 1) % count;
+        self.refresh_stats();
+    }
+
+    fn close_stats(&mut self) {
+        self.show_stats = false;
+        self.stats = None;
+    }
+
+    fn current_state(&self) -> AppState {
+        AppState {
+            camera: self.config.get_camera_name_by_url(&self.current_url),
+            show_gallery: self.show_gallery,
+            gallery_index: self.gallery_index,
+            show_stats: self.show_stats,
+            show_dashboard: self.show_dashboard,
+            brightness: self.brightness.as_ref().map(Brightness::level),
+        }
+    }
+
+    fn restore_state(&mut self, state: AppState) {
+        if let Some(url) = state
+            .camera
+            .as_deref()
+            .and_then(|name| self.config.get_camera_url_by_name(name))
+        {
+            self.current_url = url;
+        }
+
+        if state.show_gallery {
+            self.open_gallery();
+            if state.gallery_index < self.gallery_items.len() {
+                self.gallery_index = state.gallery_index;
+            }
+        } else if state.show_stats {
+            self.open_stats();
+        } else if state.show_dashboard {
+            self.show_dashboard = true;
+        }
+        if let (Some(brightness), Some(level)) = (&mut self.brightness, state.brightness) {
+            brightness.set_level(level);
+        }
+
+        self.saved_state = self.current_state();
+    }
+
+    fn save_state_if_changed(&mut self) {
+        let state = self.current_state();
+        if state != self.saved_state {
+            state.save(std::path::Path::new(STATE_PATH));
+            self.saved_state = state;
+        }
+    }
+
+    fn send_pending_email(&mut self, latest_data: Option<&VideoFrame>) {
+        let Some((event, requested_at)) = &self.pending_email else {
+            return;
+        };
+
+        let camera_url = self.config.get_camera_url_by_name(&event.camera);
+        let frame = latest_data
+            .filter(|data| Some(&data.url) == camera_url.as_ref())
+            .map(|data| data.data.clone());
+
+        let has_snapshot = event
+            .details
+            .as_ref()
+            .is_some_and(|details| details.snapshot.is_some());
+        if frame.is_none() && !has_snapshot && requested_at.elapsed().as_secs() < 10 {
+            return;
+        }
+
+        if let Some(notifier) = &mut self.email_notifier {
+            notifier.send(event, frame);
+        }
+        self.pending_email = None;
+    }
+
+    fn save_event_snapshot(&self, event: &Event, jpeg: Vec<u8>) -> String {
+        let media_store = self.media_store.clone();
+        let media_index = self.media_index.clone();
+        let bus = self.bus.clone();
+        let camera = event.camera.clone();
+        let kind = event.kind;
+
+        let now = chrono::Local::now();
+        let filename = media_store.file_name(&camera, Some(kind), now, "jpg");
+        let expected = media_store.root().join(&filename);
+
+        thread::spawn(move || {
+            match media_store.write(&filename, |path| {
+                std::fs::write(path, jpeg).map_err(|e| e.to_string())
+            }) {
+                Ok(path) => {
+                    media_index.record_media(
+                        now.naive_local(),
+                        &camera,
+                        MediaKind::Snapshot,
+                        &path,
+                        Some(kind),
+                    );
+                    bus.publish(CameraEvent::SnapshotSaved { camera, path });
+                }
+                Err(e) => {
+                    eprintln!("Erreur lors de la sauvegarde de l'image : {}", e);
+                    bus.publish(CameraEvent::SnapshotFailed { camera, error: e });
+                }
+            }
+        });
+
+        expected.to_string_lossy().to_string()
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        let details = event.details.as_ref().map(|details| {
+            let snapshot = details
+                .snapshot
+                .as_ref()
+                .map(|jpeg| self.save_event_snapshot(&event, jpeg.clone()));
+            serde_json::json!({
+                "ai_types": details.ai_types,
+                "sensitivity": details.sensitivity,
+                "snapshot": snapshot,
+            })
+            .to_string()
+        });
+        self.media_index
+            .record_event(&event.camera, event.kind, details.as_deref());
+
+        let dnd = self.dnd_active();
+        if let Some(announcer) = &self.announcer
+            && !dnd
+            && self.occupancy != Some(Occupancy::Home)
+        {
+            announcer.announce(event.kind, &event.camera);
+        }
+
+        if let Some(notifier) = &self.email_notifier
+            && notifier.wants(event.kind)
+            && self.occupancy != Some(Occupancy::Home)
+            && self.pending_email.is_none()
+        {
+            self.pending_email = Some((event.clone(), std::time::Instant::now()));
+        }
+
+        let action = match &self.config.doorbell {
+            Some(doorbell) if doorbell.camera == event.camera => doorbell.wake_action(event.kind),
+            _ if event.kind == EventKind::Visitor => WakeAction::Switch,
+            _ => WakeAction::Ignore,
+        };
+        if action == WakeAction::Ignore {
+            return;
+        }
+        if dnd {
+            println!(
+                "Ne pas déranger : {} de {} enregistré sans alerte",
+                event.kind.label(),
+                event.camera
+            );
+            return;
+        }
+
+        if event.kind == EventKind::Visitor {
+            if let Some(lan_sync) = &self.lan_sync {
+                lan_sync.announce_doorbell(&event.camera);
+            }
+            self.last_ring = Some(std::time::Instant::now());
+            if let Some(chime) = &self.chime {
+                chime.play();
+            }
+        }
+        match action {
+            WakeAction::Switch => self.show_event_camera(&event.camera),
+            _ => self.wake_display(),
+        }
+        if event.kind == EventKind::Visitor {
+            self.start_call(&event.camera);
+        }
+    }
+
+    fn ring(&mut self, camera: &str) {
+        self.last_ring = Some(std::time::Instant::now());
+        if self.dnd_active() {
+            return;
+        }
+        if let Some(chime) = &self.chime {
+            chime.play();
+        }
+        self.show_event_camera(camera);
+        self.start_call(camera);
+    }
+
+    fn dnd_active(&self) -> bool {
+        self.dnd.is_active(chrono::Local::now().naive_local())
+    }
+
+    fn set_dnd(&mut self, active: bool) {
+        self.dnd.set(chrono::Local::now().naive_local(), active);
+        println!(
+            "Ne pas déranger {}",
+            if active { "activé" } else { "désactivé" }
+        );
+    }
+
+    fn start_call(&mut self, camera: &str) {
+        let Some(config) = self.config.call.clone() else {
+            return;
+        };
+        if self.call.as_ref().is_some_and(Call::is_answered) {
+            return;
+        }
+        if let Some(url) = self.config.get_camera_url_by_name(camera)
+            && url != self.current_url
+        {
+            self.show_stream(&url);
+        }
+        self.call = Some(Call::ring(camera, config));
+    }
+
+    fn show_call(&mut self, ctx: &egui::Context) {
+        let Some(call) = &mut self.call else {
+            return;
+        };
+        let mut dismiss = false;
+
+        egui::TopBottomPanel::bottom("call_actions")
+            .frame(
+                egui::Frame::new()
+                    .fill(egui::Color32::from_gray(20))
+                    .inner_margin(20.0),
+            )
+            .show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.spacing_mut().item_spacing.x = 30.0;
+                    let button = |ui: &mut egui::Ui, text: &str, color: egui::Color32| {
+                        ui.add(
+                            egui::Button::new(
+                                RichText::new(text).size(36.).color(egui::Color32::WHITE),
+                            )
+                            .fill(color)
+                            .corner_radius(40.0)
+                            .min_size(egui::vec2(260.0, 90.0)),
+                        )
+                        .clicked()
+                    };
+
+                    if call.is_answered() {
+                        if button(ui, "📴 Raccrocher", egui::Color32::from_rgb(200, 30, 30)) {
+                            dismiss = true;
                         }
                     } else {
-                        false
+                        if call.can_answer()
+                            && button(ui, "📞 Répondre", egui::Color32::from_rgb(40, 160, 70))
+                        {
+                            call.answer();
+                        }
+                        if button(ui, "🔕 Ignorer", egui::Color32::from_gray(70)) {
+                            dismiss = true;
+                        }
                     }
-                })
-                .collect(),
-            Err(_) => Vec::new(),
+                    if call.can_open_door()
+                        && button(ui, "🔓 Ouvrir", egui::Color32::from_rgb(40, 90, 180))
+                    {
+                        call.open_door();
+                    }
+                });
+            });
+
+        egui::CentralPanel::default()
+            .frame(egui::Frame::new().fill(egui::Color32::BLACK))
+            .show(ctx, |ui| {
+                if let Some(texture) = &self.texture {
+                    display::show_image(ui, texture, DisplayMode::Fit, self.config.config.rotation);
+                } else {
+                    ui.centered_and_justified(|ui| {
+                        ui.add(egui::Spinner::new().size(64.0));
+                    });
+                }
+            });
+
+        let status = match call.remaining() {
+            Some(remaining) => format!("🔔 {} sonne · {} s", call.camera, remaining.as_secs()),
+            None => format!("📞 En ligne avec {}", call.camera),
         };
+        egui::Area::new("call_status".into())
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 20.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::new()
+                    .fill(egui::Color32::from_black_alpha(200))
+                    .inner_margin(16.0)
+                    .corner_radius(15.0)
+                    .show(ui, |ui| {
+                        ui.label(RichText::new(status).color(egui::Color32::WHITE).size(32.));
+                    });
+            });
 
-        self.gallery_images.sort();
-        self.gallery_images.reverse();
-        self.gallery_index = 0;
-        self.show_gallery = true;
-        self.gallery_texture = None;
+        if dismiss {
+            self.call = None;
+        }
     }
 
-    fn load_gallery_texture(&mut self, ctx: &egui::Context) {
-        if self.gallery_images.is_empty() {
-            self.gallery_texture = None;
+    fn show_event_camera(&mut self, camera: &str) {
+        match self.config.get_camera_url_by_name(camera) {
+            Some(url) => {
+                self.close_gallery();
+                self.close_stats();
+                self.show_dashboard = false;
+                self.show_stream(&url);
+                self.last_activity = std::time::Instant::now();
+            }
+            None => self.wake_display(),
+        }
+    }
+
+    fn wake_display(&mut self) {
+        if let Some(sender) = self.running_sender.get(&self.current_url) {
+            let _ = sender.send(true);
+        }
+        self.last_activity = std::time::Instant::now();
+    }
+
+    fn handle_command(&mut self, command: RemoteCommand) {
+        match command {
+            RemoteCommand::SwitchCamera(camera) => {
+                let Some(url) = self.config.get_camera_url_by_name(&camera) else {
+                    eprintln!("Caméra inconnue : {}", camera);
+                    return;
+                };
+                self.show_gallery = false;
+                self.show_stats = false;
+                self.show_dashboard = false;
+                self.switch_stream(&url);
+                self.last_activity = std::time::Instant::now();
+            }
+            RemoteCommand::Snapshot => {
+                if let Some(frame) = &self.current_frame {
+                    self.take_snapshot(frame);
+                    self.notification_timer = Some(std::time::Instant::now());
+                }
+            }
+            RemoteCommand::PeerCamera(camera) => {
+                if let Some(url) = self.config.get_camera_url_by_name(&camera) {
+                    self.show_stream(&url);
+                }
+            }
+            RemoteCommand::PeerDoorbell(camera) => {
+                let recently_rung = self
+                    .last_ring
+                    .is_some_and(|time| time.elapsed() < PEER_RING_SUPPRESSION);
+                if !recently_rung {
+                    self.ring(&camera);
+                }
+            }
+            RemoteCommand::CameraPower { camera, on } => self.set_camera_power(&camera, on),
+            RemoteCommand::DoNotDisturb(active) => self.set_dnd(active),
+        }
+    }
+
+    fn is_powered_off(&self, url: &str) -> bool {
+        self.config
+            .get_camera_name_by_url(url)
+            .is_some_and(|name| self.powered_off.contains(&name))
+    }
+
+    fn set_camera_power(&mut self, name: &str, on: bool) {
+        let Some(camera) = self.config.camera.iter().find(|cam| cam.name == name) else {
+            eprintln!("Caméra inconnue : {}", name);
+            return;
+        };
+        let Some(power) = &camera.power else {
+            eprintln!("Aucune commande d'alimentation pour {}", name);
+            return;
+        };
+
+        println!(
+            "{} de la caméra {}",
+            if on { "Allumage" } else { "Extinction" },
+            name
+        );
+        power::set_power(camera.name.clone(), power.clone(), on);
+        if on {
+            self.powered_off.remove(name);
+        } else {
+            self.powered_off.insert(camera.name.clone());
+        }
+
+        if let Some(sender) = self.running_sender.get(&camera.url) {
+            let _ = sender.send(on && camera.url == self.current_url);
+        }
+    }
+
+    fn repaint_interval(&self) -> Option<std::time::Duration> {
+        if self.notification_timer.is_some() {
+            return None;
+        }
+        if self.show_gallery || self.is_powered_off(&self.current_url) {
+            return Some(IDLE_REPAINT);
+        }
+        if let Some(due) = self.frame_store.next_due_in() {
+            return Some(due.min(IDLE_REPAINT));
+        }
+        match self.stream_status.get(&self.current_url) {
+            Some(StreamStatus::Playing { fps, .. }) => {
+                let fps = if fps.is_finite() && *fps > 0.0 {
+                    *fps
+                } else {
+                    DEFAULT_FPS
+                };
+                Some(std::time::Duration::from_secs_f64(1.0 / fps).min(IDLE_REPAINT))
+            }
+            _ => Some(IDLE_REPAINT),
+        }
+    }
+
+    fn show_toast(&mut self, ctx: &egui::Context) {
+        let Some(toast) = &self.toast else {
+            return;
+        };
+        if toast.shown.elapsed() >= toast.duration() {
+            self.toast = None;
+        } else if toast.show(ctx, self.config.config.controls_on_screen()) {
+            self.toast = None;
+            self.undo_delete();
+        }
+    }
+
+    fn show_system_status(&self, ctx: &egui::Context) {
+        let status = self.system_monitor.status();
+        let thresholds = &self.config.system_status;
+
+        let mut indicators = Vec::new();
+        if let Some(free) = status.free_disk_gb {
+            indicators.push((
+                format!("💾 {:.1} Go", free),
+                free < thresholds.min_free_disk_gb,
+            ));
+        }
+        if let Some(wifi) = status.wifi_percent {
+            indicators.push((format!("📶 {} %", wifi), wifi < thresholds.min_wifi_percent));
+        }
+        if let Some(temperature) = status.cpu_temperature {
+            indicators.push((
+                format!("🌡 {:.0} °C", temperature),
+                temperature > thresholds.max_cpu_temperature,
+            ));
+        }
+        for (camera, drift) in &status.clock_drift_secs {
+            if drift.abs() > thresholds.max_clock_drift_secs {
+                indicators.push((format!("🕒 {} {:+} s", camera, drift), true));
+            }
+        }
+        if indicators.is_empty() {
+            return;
+        }
+
+        egui::Area::new("system_status_overlay".into())
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 10.0))
+            .pivot(egui::Align2::RIGHT_TOP)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::new()
+                    .fill(egui::Color32::from_black_alpha(200))
+                    .inner_margin(12.0)
+                    .corner_radius(15.0)
+                    .show(ui, |ui| {
+                        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                        ui.horizontal(|ui| {
+                            ui.spacing_mut().item_spacing.x = 16.0;
+                            for (text, warning) in indicators {
+                                let color = if warning {
+                                    egui::Color32::from_rgb(240, 180, 40)
+                                } else {
+                                    egui::Color32::LIGHT_GRAY
+                                };
+                                ui.label(egui::RichText::new(text).color(color).size(18.0));
+                            }
+                        });
+                    });
+            });
+    }
+
+    fn show_brightness_slider(&mut self, ctx: &egui::Context) {
+        let controls = self.config.config.controls_on_screen();
+        let Some(brightness) = &mut self.brightness else {
             return;
+        };
+
+        egui::Area::new("brightness_slider".into())
+            .anchor(
+                egui::Align2::CENTER_TOP,
+                egui::vec2(0.0, controls.clearance().0 + 10.0),
+            )
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::new()
+                    .fill(egui::Color32::from_black_alpha(200))
+                    .inner_margin(16.0)
+                    .corner_radius(15.0)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new("☀").size(28.));
+                            ui.spacing_mut().slider_width = 320.0;
+                            let mut level = brightness.level() * 100.0;
+                            if ui
+                                .add(
+                                    egui::Slider::new(&mut level, 5.0..=100.0)
+                                        .suffix(" %")
+                                        .integer(),
+                                )
+                                .changed()
+                            {
+                                brightness.set_level(level / 100.0);
+                            }
+                        });
+                    });
+            });
+    }
+
+    fn sleep_timeout_secs(&self) -> u64 {
+        match (&self.config.daynight, self.is_night) {
+            (Some(daynight), Some(night)) => daynight.sleep_timeout_secs(night),
+            _ => 15,
+        }
+    }
+
+    fn update_day_night(&mut self) {
+        let Some(daynight) = &self.config.daynight else {
+            return;
+        };
+
+        let night = daynight.is_night(chrono::Local::now());
+        if self.is_night == Some(night) {
+            return;
+        }
+        self.is_night = Some(night);
+
+        for camera in &self.config.camera {
+            let Some(reolink) = camera.reolink.clone() else {
+                continue;
+            };
+            if !reolink.set_day_night {
+                continue;
+            }
+
+            let name = camera.name.clone();
+            thread::spawn(move || {
+                if let Err(e) = ReolinkClient::new(reolink).set_day_night(night) {
+                    eprintln!(
+                        "Impossible de changer le mode jour/nuit de {} : {}",
+                        name, e
+                    );
+                }
+            });
         }
+    }
+}
+
+fn main() -> Result<(), eframe::Error> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("probe") {
+        probe::run(&args[2..], CONFIG_PATH);
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("import") {
+        import::run(&args[2..], CONFIG_PATH);
+        return Ok(());
+    }
+    if let Some(command @ ("backup" | "restore")) = args.get(1).map(String::as_str) {
+        backup::run(command, &args[2..], CONFIG_PATH, STATE_PATH);
+        return Ok(());
+    }
+    if args.iter().any(|arg| arg == "--supervise") {
+        supervisor::run(&args);
+    }
 
-        if let Some(path) = self.gallery_images.get(self.gallery_index) {
-            if let Ok(img) = image::open(path) {
-                let img = img.to_rgba8();
-                let size = [img.width() as usize, img.height() as usize];
-                let pixels = img.into_raw();
-                let color_image = egui::ColorImage::from_rgba_unmultiplied(size, &pixels);
-                let id = format!("gallery:{}", path.display());
-                self.gallery_texture =
-                    Some(ctx.load_texture(&id, color_image, egui::TextureOptions::LINEAR));
-            } else {
-                self.gallery_texture = None;
-            }
+    let content = std::fs::read_to_string(CONFIG_PATH).expect("Impossible de lire le fichier");
+    let mut parsed: RootConfig = toml::from_str(&content).expect("Impossible de parser le fichier");
+    if let Some(address) = parsed.config.bind_address {
+        net::bind_camera_traffic(address);
+    }
+    parsed.resolve_remote_cameras();
+
+    let mut doorbell_simulator = None;
+    if args.iter().any(|arg| arg == "--simulate-doorbell") {
+        let camera = parsed
+            .doorbell
+            .as_ref()
+            .map(|doorbell| doorbell.camera.clone())
+            .or_else(|| parsed.get_camera_names().into_iter().next())
+            .unwrap_or_default();
+        if let Some((simulator, config)) = DoorbellSimulator::spawn(camera) {
+            parsed.doorbell = Some(config);
+            doorbell_simulator = Some(simulator);
         }
     }
 
-    fn gallery_next(&mut self) {
-        if self.gallery_images.is_empty() {
-            return;
+    let capture_path = std::path::Path::new(&parsed.config.capture_path);
+    let media_index = MediaIndex::open(&capture_path.join("index.sqlite"))
+        .or_else(|e| {
+            eprintln!("Impossible d'ouvrir l'index des médias : {}", e);
+            MediaIndex::open_in_memory()
+        })
+        .expect("Impossible de créer l'index des médias");
+    media_index.import_event_log(&capture_path.join("events.csv"));
+    let media_store = storage::open(
+        &parsed.storage,
+        capture_path,
+        &parsed.config.filename_template,
+    );
+    media_index.index_existing_snapshots(media_store.root(), &parsed.get_camera_names());
+    let trash = Trash::new(media_store.root(), &parsed.trash);
+    trash.purge();
+
+    let bus = EventBus::default();
+    let bus_receiver = bus.subscribe_filtered(|event| !matches!(event, CameraEvent::FrameReady(_)));
+    let frame_budget = FrameBudget::default();
+    let frame_store = FrameStore::spawn(&bus, parsed.config.drop_late_frames, frame_budget.clone());
+    frame_store.set_active(&parsed.get_first_camera_url().unwrap_or_default());
+
+    let doorbell_poll_rate = parsed
+        .doorbell
+        .clone()
+        .map(|config| DoorbellMonitor::spawn(config, bus.clone(), parsed.email.clone()));
+
+    for camera in &parsed.camera {
+        if let (Some(reolink), Some(patrol)) = (&camera.reolink, &camera.patrol) {
+            ptz::spawn_patrol(
+                camera.name.clone(),
+                ReolinkClient::new(reolink.clone()),
+                camera.ptz_presets.clone(),
+                patrol.clone(),
+            );
         }
-        self.gallery_index = (self.gallery_index + 1) % self.gallery_images.len();
-        self.gallery_texture = None;
     }
 
-    fn gallery_previous(&mut self) {
-        if self.gallery_images.is_empty() {
-            return;
-        }
-        if self.gallery_index == 0 {
-            self.gallery_index = self.gallery_images.len() - 1;
-        } else {
-            self.gallery_index -= 1;
-        }
-        self.gallery_texture = None;
+    if let Some(home_assistant_config) = parsed.home_assistant.clone() {
+        HomeAssistant::spawn(
+            home_assistant_config,
+            parsed.get_camera_names(),
+            bus.clone(),
+        );
     }
 
-    fn close_gallery(&mut self) {
-        self.show_gallery = false;
-        self.gallery_texture = None;
+    if let Some(web_config) = parsed.web.clone() {
+        web::spawn(
+            web_config,
+            parsed.camera_urls_by_name(),
+            parsed.restream_urls_by_name(),
+            frame_store.clone(),
+            parsed.config.use_tcp_for_rtsp,
+            bus.clone(),
+        );
     }
-}
 
-fn main() -> Result<(), eframe::Error> {
-    let content = std::fs::read_to_string("config.toml").expect("Impossible de lire le fichier");
-    let parsed: RootConfig = toml::from_str(&content).expect("Impossible de parser le fichier");
+    power::spawn_schedule(
+        parsed
+            .camera
+            .iter()
+            .filter_map(|cam| Some((cam.name.clone(), cam.power.clone()?)))
+            .collect(),
+        bus.clone(),
+    );
+
+    let lan_sync = parsed
+        .sync
+        .clone()
+        .and_then(|config| LanSync::spawn(config, bus.clone()));
+
+    let system_monitor = SystemMonitor::spawn(
+        std::path::PathBuf::from(&parsed.config.capture_path),
+        parsed.reolink_clients(),
+    );
+
+    let health = parsed.health_report.clone().map(|config| {
+        HealthMonitor::spawn(
+            config,
+            parsed
+                .camera
+                .iter()
+                .map(|cam| (cam.url.clone(), cam.name.clone()))
+                .collect(),
+            parsed.config.capture_path.clone(),
+            parsed.email.clone(),
+            &bus,
+        )
+    });
+
+    let mut rules = parsed.rule.clone();
+    if let Some(presence_config) = parsed.presence.clone() {
+        rules.extend(presence_config.away_rules());
+        presence::spawn(presence_config, bus.clone());
+    }
 
-    let (packet_sender, packet_receiver) = unbounded::<VideoFrame>();
+    Automation::new(
+        rules,
+        parsed.reolink_clients(),
+        parsed.recording_sources(),
+        media_store.clone(),
+        parsed.config.use_tcp_for_rtsp,
+        media_index.clone(),
+        bus.clone(),
+    )
+    .spawn();
 
     let mut video_app = VideoApp {
         current_url: parsed.get_first_camera_url().unwrap_or_default(),
         running_sender: HashMap::default(),
-        packet_receiver: packet_receiver.clone(),
+        bus,
+        bus_receiver,
+        stream_status: HashMap::default(),
+        analytics: HashMap::default(),
         texture: None,
+        frame_store: frame_store.clone(),
+        frame_budget,
+        dropped_frames: 0,
+        latency: LatencyMeter::default(),
+        current_frame: None,
         notification_timer: None,
+        capture_view: false,
+        view_rect: None,
+        low_power_since: None,
+        repaint: None,
+        toast: None,
+        chime: parsed.chime.clone().map(Chime::new),
+        announcer: parsed.tts.clone().map(Announcer::new),
+        email_notifier: parsed.email.clone().map(EmailNotifier::new),
+        pending_email: None,
+        media_index,
+        media_store,
+        show_stats: false,
+        show_dashboard: false,
+        dashboard: Dashboard::default(),
+        stats: None,
+        stats_camera: 0,
+        saved_state: AppState::default(),
+        is_night: None,
+        brightness: parsed
+            .brightness
+            .clone()
+            .map(|config| Brightness::spawn(config, 1.0)),
+        show_brightness: false,
+        spotlight_on: std::collections::HashSet::new(),
+        powered_off: std::collections::HashSet::new(),
+        health,
+        system_monitor,
+        doorbell_health: None,
+        doorbell_simulator,
+        doorbell_poll_rate,
+        lan_sync,
+        last_ring: None,
+        call: None,
+        dnd: DoNotDisturb::new(parsed.dnd.as_ref()),
+        occupancy: None,
         config: parsed,
         show_gallery: false,
-        gallery_images: Vec::new(),
+        gallery_items: Vec::new(),
+        gallery_filter: MediaFilter::default(),
         gallery_index: 0,
         gallery_texture: None,
+        gallery_zoom: Zoom::default(),
+        gallery_selection: std::collections::HashSet::new(),
+        trash,
+        deleted: Vec::new(),
+        gallery_compare: None,
+        compare_texture: None,
+        compare_blend: None,
+        playback: None,
         last_activity: std::time::Instant::now(),
     };
 
-    for path in video_app.config.get_camera_urls().iter() {
-        let sender_clone = packet_sender.clone();
-        let path_string = path.to_string();
-        let (stop_sender, stop_receiver) = unbounded::<bool>();
-        let running = path_string == video_app.current_url;
-
-        thread::spawn(move || {
-            let video_stream = VideoStream {
-                url: path_string.clone(),
-                packet_sender: sender_clone.clone(),
-                stop_receiver,
-                running,
-            };
-            let _ = run_decoder_managed(
-                video_stream,
-                video_app.config.config.has_to_wait_for_keyframe,
-                video_app.config.config.use_tcp_for_rtsp,
-            );
-        });
-
-        video_app
-            .running_sender
-            .insert(path.to_string(), stop_sender);
-    }
+    video_app.restore_state(AppState::load(std::path::Path::new(STATE_PATH)));
 
     let options = eframe::NativeOptions {
         ..Default::default()
@@ -268,7 +1829,11 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "Security Camera Viewer",
         options,
-        Box::new(|_cc| Ok(Box::new(video_app))),
+        Box::new(|cc| {
+            video_app.repaint = Some(cc.egui_ctx.clone());
+            video_app.spawn_decoders();
+            Ok(Box::new(video_app))
+        }),
     )
 }
 
@@ -285,6 +1850,33 @@ impl eframe::App for VideoApp {
             }
         });
 
+        if ctx.input(|i| i.key_pressed(egui::Key::R)) {
+            self.restart_streams();
+        }
+
+        let screenshot = ctx.input(|i| {
+            i.events.iter().find_map(|e| match e {
+                egui::Event::Screenshot { image, .. } => Some((image.clone(), i.pixels_per_point)),
+                _ => None,
+            })
+        });
+        if let Some((image, pixels_per_point)) = screenshot {
+            self.save_view_capture(&image, pixels_per_point);
+            self.notification_timer = Some(std::time::Instant::now());
+        }
+
+        if let Some(simulator) = &self.doorbell_simulator {
+            for (key, kind) in [
+                (egui::Key::V, EventKind::Visitor),
+                (egui::Key::P, EventKind::Person),
+                (egui::Key::M, EventKind::Motion),
+            ] {
+                if ctx.input(|i| i.key_pressed(key)) {
+                    simulator.trigger(kind);
+                }
+            }
+        }
+
         ctx.output_mut(|o| {
             o.cursor_icon = if self.config.config.cursor_visible {
                 egui::CursorIcon::Default
@@ -293,64 +1885,213 @@ impl eframe::App for VideoApp {
             };
         });
 
+        while let Ok(event) = self.bus_receiver.try_recv() {
+            match event {
+                CameraEvent::FrameReady(_) => {}
+                CameraEvent::Detected(event) => self.handle_event(event),
+                CameraEvent::StreamStatus(update) => {
+                    self.dashboard.record(&update);
+                    self.stream_status.insert(update.url, update.status);
+                }
+                CameraEvent::SnapshotSaved { camera, path } => {
+                    println!("Capture enregistrée pour {} : {}", camera, path.display());
+                    self.toast = Some(Toast::success(format!("Capture enregistrée ({})", camera)));
+                    if self.show_gallery {
+                        self.apply_gallery_filter();
+                    }
+                }
+                CameraEvent::SnapshotFailed { camera, error } => {
+                    self.toast = Some(Toast::error(format!(
+                        "Capture de {} perdue : {}",
+                        camera, error
+                    )));
+                }
+                CameraEvent::Command(command) => self.handle_command(command),
+                CameraEvent::DoorbellStatus(health) => self.doorbell_health = Some(health),
+                CameraEvent::Presence(occupancy) => self.occupancy = Some(occupancy),
+                CameraEvent::Analytics { url, objects } => {
+                    self.analytics
+                        .insert(url, (std::time::Instant::now(), objects));
+                }
+            }
+        }
+
+        self.save_state_if_changed();
+
+        if self.is_night == Some(true)
+            && let Some(daynight) = &self.config.daynight
+            && daynight.night_dim > 0.0
+        {
+            let alpha = (daynight.night_dim.clamp(0.0, 1.0) * 255.0) as u8;
+            ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Tooltip,
+                egui::Id::new("night_dim_layer"),
+            ))
+            .rect_filled(
+                ctx.viewport_rect(),
+                0.0,
+                egui::Color32::from_black_alpha(alpha),
+            );
+        }
+
         let has_activity = ctx.input(|i| {
             !i.events.is_empty() || i.pointer.any_click() || i.pointer.delta().length() > 0.0
         });
 
+        self.update_day_night();
+        let sleep_timeout = self.sleep_timeout_secs();
+
         if has_activity {
-            if self.last_activity.elapsed().as_secs() >= 15 {
-                 if let Some(sender) = self.running_sender.get(&self.current_url) {
-                    let _ = sender.send(true);
-                }
+            if self.last_activity.elapsed().as_secs() >= sleep_timeout
+                && let Some(sender) = self.running_sender.get(&self.current_url)
+            {
+                let _ = sender.send(true);
             }
             self.last_activity = std::time::Instant::now();
         }
 
-        if self.last_activity.elapsed().as_secs() >= 15 {
-            for sender in self.running_sender.values() {
-                let _ = sender.send(false);
+        if let Some(call) = &mut self.call
+            && call.is_over()
+        {
+            self.call = None;
+        }
+        if self.call.is_some() {
+            self.last_activity = std::time::Instant::now();
+        }
+
+        let asleep = self.last_activity.elapsed().as_secs() >= sleep_timeout;
+        if let Some(brightness) = &mut self.brightness {
+            brightness.update(
+                self.is_night == Some(true),
+                self.last_activity.elapsed().as_secs(),
+            );
+        }
+        if let Some(poll_rate) = &self.doorbell_poll_rate {
+            poll_rate.set_display_awake(!asleep);
+        }
+        if asleep {
+            if self.low_power_since.is_none() {
+                println!("Écran en veille, rendu suspendu");
+                self.low_power_since = Some(std::time::Instant::now());
+                for sender in self.running_sender.values() {
+                    let _ = sender.send(false);
+                }
                 self.texture = None;
+                self.current_frame = None;
+            }
+            self.frame_store.clear();
+            self.send_pending_email(None);
+            ctx.request_repaint_after(ASLEEP_REPAINT);
+            return;
+        }
+        if let Some(since) = self.low_power_since.take() {
+            let duration = since.elapsed();
+            println!(
+                "Réveil de l'écran après {} min de veille",
+                duration.as_secs() / 60
+            );
+            if let Some(health) = &self.health {
+                health.add_low_power(duration);
             }
         }
 
-        let mut latest_data = None;
-        while let Ok(data) = self.packet_receiver.try_recv() {
-            if self.current_url != data.url {
-                continue;
-            }
-            latest_data = Some(data);
+        let latest_data = self.frame_store.next_frame();
+        let dropped = self.frame_store.take_dropped();
+        self.dropped_frames += dropped;
+        if let Some(health) = &self.health {
+            health.add_dropped_frames(dropped);
         }
+        self.send_pending_email(latest_data.as_deref());
 
-        if let Some(data) = latest_data.as_ref() {
+        if let Some(data) = latest_data {
             let color_image = egui::ColorImage::from_rgba_unmultiplied(
                 [WIDTH as usize, HEIGHT as usize],
                 &data.data,
             );
             self.texture =
                 Some(ctx.load_texture("video_frame", color_image, egui::TextureOptions::LINEAR));
+            self.latency.record(&data, std::time::Instant::now());
+            self.current_frame = Some(data);
+        }
+
+        if self.call.is_some() {
+            self.show_call(ctx);
+            ctx.request_repaint_after(IDLE_REPAINT);
+            return;
+        }
+
+        if self.show_gallery {
+            egui::TopBottomPanel::top("gallery_filters")
+                .frame(
+                    egui::Frame::new()
+                        .fill(egui::Color32::from_gray(20))
+                        .inner_margin(10.0),
+                )
+                .show(ctx, |ui| {
+                    if self.show_gallery_filters(ui) {
+                        self.apply_gallery_filter();
+                    }
+                    self.show_compare_controls(ui);
+                });
+
+            if self.playback.is_none() && !self.gallery_items.is_empty() {
+                let frame = egui::Frame::new()
+                    .fill(egui::Color32::from_gray(20))
+                    .inner_margin(10.0);
+                if display::is_portrait(ctx) {
+                    egui::TopBottomPanel::top("gallery_days")
+                        .resizable(false)
+                        .exact_height(ctx.viewport_rect().height() * 0.3)
+                        .frame(frame)
+                        .show(ctx, |ui| self.show_gallery_days(ui));
+                } else {
+                    egui::SidePanel::left("gallery_days")
+                        .resizable(false)
+                        .default_width(320.0)
+                        .frame(frame)
+                        .show(ctx, |ui| self.show_gallery_days(ui));
+                }
+            }
         }
 
         egui::CentralPanel::default()
             .frame(egui::Frame::new().fill(egui::Color32::BLACK))
             .show(ctx, |ui| {
-                if self.show_gallery {
+                if self.show_stats {
+                    let title = self.stats_title();
+                    if let Some(stats) = &self.stats {
+                        stats.show(ui, &title);
+                    }
+                } else if self.show_dashboard {
+                    let cameras: Vec<(String, String)> = self
+                        .config
+                        .camera
+                        .iter()
+                        .map(|cam| (cam.name.clone(), cam.url.clone()))
+                        .collect();
+                    self.dashboard.show(ui, &cameras, &self.frame_store);
+                } else if self.show_gallery {
                     if self.gallery_texture.is_none() {
                         self.load_gallery_texture(ctx);
                     }
 
-                    if let Some(texture) = &self.gallery_texture {
-                        let available = ui.available_size();
-                        let image_size = texture.size_vec2();
-                        let image_ratio = image_size.x / image_size.y;
-                        let final_size = if (available.x / available.y) > image_ratio {
-                            egui::vec2(available.y * image_ratio, available.y)
-                        } else {
-                            egui::vec2(available.x, available.x / image_ratio)
-                        };
-
-                        ui.centered_and_justified(|ui| {
-                            ui.add(egui::Image::new(texture).fit_to_exact_size(final_size));
-                        });
+                    if let Some(playback) = &mut self.playback {
+                        playback.show(ui);
+                    } else if self.gallery_compare.is_some() {
+                        self.show_compare(ui, ctx);
+                    } else if let Some(texture) = &self.gallery_texture {
+                        let mode = self
+                            .gallery_items
+                            .get(self.gallery_index)
+                            .map(|item| self.config.display_mode(&item.camera))
+                            .unwrap_or_default();
+                        display::show_zoomable_image(
+                            ui,
+                            texture,
+                            mode,
+                            self.config.config.rotation,
+                            &mut self.gallery_zoom,
+                        );
                     } else {
                         ui.centered_and_justified(|ui| {
                             ui.label(RichText::new("Aucune image dans le dossier...").size(32.));
@@ -358,17 +2099,56 @@ impl eframe::App for VideoApp {
                     }
                 } else {
                     if let Some(texture) = &self.texture {
-                        let available = ui.available_size();
-                        let image_size = texture.size_vec2();
-                        let image_ratio = image_size.x / image_size.y;
-                        let final_size = if (available.x / available.y) > image_ratio {
-                            egui::vec2(available.y * image_ratio, available.y)
-                        } else {
-                            egui::vec2(available.x, available.x / image_ratio)
-                        };
-
-                        ui.centered_and_justified(|ui| {
-                            ui.add(egui::Image::new(texture).fit_to_exact_size(final_size));
+                        let mode = self
+                            .config
+                            .get_camera_name_by_url(&self.current_url)
+                            .map(|name| self.config.display_mode(&name))
+                            .unwrap_or_default();
+                        let (rect, uv) =
+                            display::show_image(ui, texture, mode, self.config.config.rotation);
+                        self.view_rect = Some(rect.intersect(ui.clip_rect()));
+                        if let Some((received, objects)) = self.analytics.get(&self.current_url)
+                            && received.elapsed() < ANALYTICS_TTL
+                        {
+                            display::draw_objects(
+                                ui,
+                                rect,
+                                uv,
+                                self.config.config.rotation,
+                                objects,
+                            );
+                        }
+                    } else if let Some(name) = self
+                        .config
+                        .get_camera_name_by_url(&self.current_url)
+                        .filter(|name| self.powered_off.contains(name))
+                    {
+                        ui.vertical_centered(|ui| {
+                            ui.add_space(ui.available_height() / 3.0);
+                            ui.label(RichText::new(format!("⏻ {} est éteinte", name)).size(32.));
+                            ui.add_space(20.0);
+                            if ui.button(RichText::new("⏻ Allumer").size(28.)).clicked() {
+                                self.set_camera_power(&name, true);
+                            }
+                        });
+                    } else if let Some(StreamStatus::Error(message)) =
+                        self.stream_status.get(&self.current_url)
+                    {
+                        let message = message.clone();
+                        ui.vertical_centered(|ui| {
+                            ui.add_space(ui.available_height() / 3.0);
+                            ui.label(
+                                RichText::new(format!("⚠ {}", message))
+                                    .color(egui::Color32::from_rgb(230, 60, 60))
+                                    .size(32.),
+                            );
+                            ui.add_space(20.0);
+                            if ui
+                                .button(RichText::new("⟳ Redémarrer les flux").size(28.))
+                                .clicked()
+                            {
+                                self.restart_streams();
+                            }
                         });
                     } else {
                         ui.centered_and_justified(|ui| {
@@ -381,148 +2161,292 @@ impl eframe::App for VideoApp {
         let btn_size = egui::vec2(130.0, 130.0);
         let capture_radius = 44.0;
 
-        egui::Area::new("controls".into())
-            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -10.0))
-            .show(ctx, |ui| {
-                egui::Frame::new()
-                    .fill(egui::Color32::from_black_alpha(170))
-                    .corner_radius(50.0)
-                    .inner_margin(egui::Margin::symmetric(5, 2))
-                    .show(ui, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.spacing_mut().item_spacing.x = 40.0;
+        let capturing_view = std::mem::take(&mut self.capture_view);
+        if capturing_view && let Some(data) = &self.current_frame {
+            self.take_snapshot(data);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::default()));
+        }
 
-                            {
-                                let (rect, resp) =
-                                    ui.allocate_exact_size(btn_size, egui::Sense::click());
-
-                                if resp.hovered() {
-                                    ui.painter().circle_filled(
-                                        rect.center(),
-                                        50.0,
-                                        egui::Color32::from_white_alpha(20),
-                                    );
-                                }
+        let rotation = self.config.config.rotation;
+        let controls = self.config.config.controls_on_screen();
+        let controls_layout = self.config.config.controls.layout(rotation);
+        let controls_area = match controls.anchor() {
+            Some((align, offset)) => egui::Area::new("controls".into()).anchor(align, offset),
+            None => {
+                let screen = ctx.viewport_rect();
+                egui::Area::new("controls".into())
+                    .movable(true)
+                    .pivot(egui::Align2::CENTER_BOTTOM)
+                    .default_pos(egui::pos2(screen.center().x, screen.bottom() - 10.0))
+            }
+        };
 
-                                ui.painter().text(
+        controls_area.show(ctx, |ui| {
+            if capturing_view {
+                ui.set_invisible();
+            }
+            egui::Frame::new()
+                .fill(egui::Color32::from_black_alpha(170))
+                .corner_radius(50.0)
+                .inner_margin(egui::Margin::symmetric(5, 2))
+                .show(ui, |ui| {
+                    ui.with_layout(controls_layout, |ui| {
+                        ui.spacing_mut().item_spacing = if controls_layout.is_horizontal() {
+                            egui::vec2(40.0, 0.0)
+                        } else {
+                            egui::Vec2::ZERO
+                        };
+
+                        {
+                            let (rect, resp) =
+                                ui.allocate_exact_size(btn_size, egui::Sense::click());
+
+                            if resp.hovered() {
+                                ui.painter().circle_filled(
                                     rect.center(),
-                                    egui::Align2::CENTER_CENTER,
-                                    "◀",
-                                    egui::FontId::proportional(64.0),
-                                    egui::Color32::WHITE,
+                                    50.0,
+                                    egui::Color32::from_white_alpha(20),
                                 );
+                            }
 
-                                if resp.clicked() {
-                                    if self.show_gallery {
-                                        self.gallery_previous();
-                                        self.load_gallery_texture(ctx);
-                                    } else {
-                                        self.previous_camera();
-                                    }
+                            display::paint_icon(ui.painter(), rect.center(), "◀", 64.0, rotation);
+
+                            if resp.clicked() {
+                                if self.show_stats {
+                                    self.stats_previous();
+                                } else if self.show_gallery {
+                                    self.gallery_previous();
+                                    self.load_gallery_texture(ctx);
+                                } else {
+                                    self.previous_camera();
                                 }
                             }
+                        }
 
-                            if !self.show_gallery {
-                                let (rect, resp) =
-                                    ui.allocate_exact_size(btn_size, egui::Sense::click());
+                        if !self.show_gallery && !self.show_stats && !self.show_dashboard {
+                            let (rect, resp) =
+                                ui.allocate_exact_size(btn_size, egui::Sense::click());
+
+                            ui.painter().circle_filled(
+                                rect.center() + egui::vec2(0.0, 4.0),
+                                capture_radius + 4.0,
+                                egui::Color32::from_black_alpha(90),
+                            );
+
+                            let color = if resp.hovered() {
+                                egui::Color32::from_rgb(230, 60, 60)
+                            } else {
+                                egui::Color32::from_rgb(200, 30, 30)
+                            };
+
+                            ui.painter()
+                                .circle_filled(rect.center(), capture_radius, color);
+
+                            ui.painter().circle_stroke(
+                                rect.center(),
+                                capture_radius - 10.0,
+                                egui::Stroke::new(3.0, egui::Color32::WHITE),
+                            );
+
+                            if resp.secondary_clicked() {
+                                self.capture_view = true;
+                                ctx.request_repaint();
+                            } else if resp.clicked()
+                                && let Some(data) = &self.current_frame
+                            {
+                                self.take_snapshot(data);
+                                self.notification_timer = Some(std::time::Instant::now());
+                            }
+                        }
+                        if !self.show_stats && !self.show_dashboard {
+                            let (rect, resp) =
+                                ui.allocate_exact_size(btn_size, egui::Sense::click());
 
+                            if resp.hovered() {
                                 ui.painter().circle_filled(
-                                    rect.center() + egui::vec2(0.0, 4.0),
-                                    capture_radius + 4.0,
-                                    egui::Color32::from_black_alpha(90),
+                                    rect.center(),
+                                    50.0,
+                                    egui::Color32::from_white_alpha(20),
                                 );
+                            }
 
-                                let color = if resp.hovered() {
-                                    egui::Color32::from_rgb(230, 60, 60)
+                            display::paint_icon(
+                                ui.painter(),
+                                rect.center(),
+                                if self.show_gallery { "❌" } else { "🖼" },
+                                48.0,
+                                rotation,
+                            );
+
+                            if resp.clicked() {
+                                if self.show_gallery {
+                                    self.close_gallery();
                                 } else {
-                                    egui::Color32::from_rgb(200, 30, 30)
-                                };
+                                    self.open_gallery();
+                                    self.load_gallery_texture(ctx);
+                                }
+                            }
+                        }
 
-                                ui.painter()
-                                    .circle_filled(rect.center(), capture_radius, color);
+                        if !self.show_gallery && !self.show_dashboard {
+                            let (rect, resp) =
+                                ui.allocate_exact_size(btn_size, egui::Sense::click());
 
-                                ui.painter().circle_stroke(
+                            if resp.hovered() {
+                                ui.painter().circle_filled(
                                     rect.center(),
-                                    capture_radius - 10.0,
-                                    egui::Stroke::new(3.0, egui::Color32::WHITE),
+                                    50.0,
+                                    egui::Color32::from_white_alpha(20),
                                 );
+                            }
 
-                                if resp.clicked() {
-                                    if !self.show_gallery {
-                                        if let Some(data) = latest_data {
-                                            self.take_snapshot(&data);
-                                            self.notification_timer =
-                                                Some(std::time::Instant::now());
-                                        }
-                                    }
+                            display::paint_icon(
+                                ui.painter(),
+                                rect.center(),
+                                if self.show_stats { "❌" } else { "📊" },
+                                48.0,
+                                rotation,
+                            );
+
+                            if resp.clicked() {
+                                if self.show_stats {
+                                    self.close_stats();
+                                } else {
+                                    self.open_stats();
                                 }
                             }
-                            {
-                                let (rect, resp) =
-                                    ui.allocate_exact_size(btn_size, egui::Sense::click());
-
-                                if resp.hovered() {
-                                    ui.painter().circle_filled(
-                                        rect.center(),
-                                        50.0,
-                                        egui::Color32::from_white_alpha(20),
-                                    );
-                                }
+                        }
 
-                                ui.painter().text(
+                        if !self.show_gallery && !self.show_stats {
+                            let (rect, resp) =
+                                ui.allocate_exact_size(btn_size, egui::Sense::click());
+
+                            if resp.hovered() {
+                                ui.painter().circle_filled(
                                     rect.center(),
-                                    egui::Align2::CENTER_CENTER,
-                                    if self.show_gallery { "❌" } else { "🖼" },
-                                    egui::FontId::proportional(48.0),
-                                    egui::Color32::WHITE,
+                                    50.0,
+                                    egui::Color32::from_white_alpha(20),
                                 );
+                            }
 
-                                if resp.clicked() {
-                                    if self.show_gallery {
-                                        self.close_gallery();
-                                    } else {
-                                        self.open_gallery();
-                                        self.load_gallery_texture(ctx);
-                                    }
-                                }
+                            display::paint_icon(
+                                ui.painter(),
+                                rect.center(),
+                                if self.show_dashboard { "❌" } else { "🩺" },
+                                48.0,
+                                rotation,
+                            );
+
+                            if resp.clicked() {
+                                self.show_dashboard = !self.show_dashboard;
                             }
+                        }
 
-                            {
-                                let (rect, resp) =
-                                    ui.allocate_exact_size(btn_size, egui::Sense::click());
-
-                                if resp.hovered() {
-                                    ui.painter().circle_filled(
-                                        rect.center(),
-                                        50.0,
-                                        egui::Color32::from_white_alpha(20),
-                                    );
-                                }
+                        if self.brightness.is_some()
+                            && !self.show_gallery
+                            && !self.show_stats
+                            && !self.show_dashboard
+                        {
+                            let (rect, resp) =
+                                ui.allocate_exact_size(btn_size, egui::Sense::click());
+
+                            if resp.hovered() || self.show_brightness {
+                                ui.painter().circle_filled(
+                                    rect.center(),
+                                    50.0,
+                                    egui::Color32::from_white_alpha(20),
+                                );
+                            }
+
+                            display::paint_icon(ui.painter(), rect.center(), "☀", 48.0, rotation);
+
+                            if resp.clicked() {
+                                self.show_brightness = !self.show_brightness;
+                            }
+                        }
+
+                        {
+                            let (rect, resp) =
+                                ui.allocate_exact_size(btn_size, egui::Sense::click());
 
-                                ui.painter().text(
+                            if resp.hovered() {
+                                ui.painter().circle_filled(
                                     rect.center(),
-                                    egui::Align2::CENTER_CENTER,
-                                    "▶",
-                                    egui::FontId::proportional(64.0),
-                                    egui::Color32::WHITE,
+                                    50.0,
+                                    egui::Color32::from_white_alpha(20),
                                 );
+                            }
 
-                                if resp.clicked() {
-                                    if self.show_gallery {
-                                        self.gallery_next();
-                                        self.load_gallery_texture(ctx);
-                                    } else {
-                                        self.next_camera();
-                                    }
+                            display::paint_icon(ui.painter(), rect.center(), "▶", 64.0, rotation);
+
+                            if resp.clicked() {
+                                if self.show_stats {
+                                    self.stats_next();
+                                } else if self.show_gallery {
+                                    self.gallery_next();
+                                    self.load_gallery_texture(ctx);
+                                } else {
+                                    self.next_camera();
                                 }
                             }
-                        });
+                        }
                     });
-            });
+                });
+        });
 
-        if self.show_gallery {
+        if self.show_gallery
+            && self.playback.is_none()
+            && let Some(item) = self.gallery_items.get(self.gallery_index)
+        {
+            let icon = match item.kind {
+                MediaKind::Snapshot => "📷",
+                MediaKind::Clip => "🎞",
+            };
+            let mut caption = format!(
+                "{} {} — {}",
+                icon,
+                item.camera,
+                item.timestamp.format("%d/%m/%Y %H:%M:%S")
+            );
+            if let Some(event) = item.event {
+                caption.push_str(&format!(" ({})", event.name()));
+            }
+
+            egui::Area::new("gallery_caption_overlay".into())
+                .anchor(
+                    egui::Align2::CENTER_BOTTOM,
+                    egui::vec2(0.0, -self.config.config.controls_on_screen().clearance().1),
+                )
+                .pivot(egui::Align2::CENTER_BOTTOM)
+                .order(egui::Order::Foreground)
+                .show(ctx, |ui| {
+                    egui::Frame::new()
+                        .fill(egui::Color32::from_black_alpha(200))
+                        .inner_margin(16.0)
+                        .corner_radius(15.0)
+                        .show(ui, |ui| {
+                            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                            ui.label(
+                                egui::RichText::new(caption)
+                                    .color(egui::Color32::WHITE)
+                                    .size(24.0),
+                            )
+                        });
+                });
+        }
+
+        self.show_system_status(ctx);
+        self.show_toast(ctx);
+
+        if self.show_gallery || self.show_stats || self.show_dashboard {
+            if self.toast.is_some() {
+                ctx.request_repaint_after(IDLE_REPAINT);
+            }
             return;
         }
+        if self.show_brightness {
+            self.show_brightness_slider(ctx);
+        }
 
         let cam_index = self
             .config
@@ -531,9 +2455,70 @@ impl eframe::App for VideoApp {
             .position(|p| p == &self.current_url)
             .unwrap_or(0);
         let cam_name = self.config.get_camera_names()[cam_index].clone();
+        let network = match self.stream_status.get(&self.current_url) {
+            Some(StreamStatus::Playing { network, .. }) => Some(*network),
+            _ => None,
+        };
+
+        let doorbell_status = self.doorbell_health.as_ref().map(|health| {
+            if health.consecutive_failures == 0 {
+                (egui::Color32::from_rgb(80, 200, 120), "🔔".to_string())
+            } else {
+                let since = health
+                    .last_success
+                    .map(|time| format!(" depuis {}", time.format("%H:%M")))
+                    .unwrap_or_default();
+                let color = if health.unreachable {
+                    egui::Color32::from_rgb(230, 60, 60)
+                } else {
+                    egui::Color32::from_rgb(240, 180, 40)
+                };
+                (color, format!("🔔 injoignable{}", since))
+            }
+        });
+        let dnd = self.dnd_active();
+        let mut toggle_dnd = false;
+
+        egui::Area::new("doorbell_status_overlay".into())
+            .anchor(egui::Align2::LEFT_TOP, egui::vec2(10.0, 10.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::new()
+                    .fill(egui::Color32::from_black_alpha(200))
+                    .inner_margin(12.0)
+                    .corner_radius(15.0)
+                    .show(ui, |ui| {
+                        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                        ui.horizontal(|ui| {
+                            ui.spacing_mut().item_spacing.x = 16.0;
+                            if let Some((color, text)) = doorbell_status {
+                                ui.label(egui::RichText::new(text).color(color).size(20.0));
+                            }
+                            let (text, color) = if dnd {
+                                ("🌙 Ne pas déranger", egui::Color32::from_rgb(240, 180, 40))
+                            } else {
+                                ("🌙", egui::Color32::GRAY)
+                            };
+                            toggle_dnd = ui
+                                .add(
+                                    egui::Button::new(
+                                        egui::RichText::new(text).color(color).size(20.0),
+                                    )
+                                    .frame(false),
+                                )
+                                .clicked();
+                        });
+                    });
+            });
+        if toggle_dnd {
+            self.set_dnd(!dnd);
+        }
 
         egui::Area::new("camera_name_overlay".into())
-            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 10.0))
+            .anchor(
+                egui::Align2::CENTER_TOP,
+                egui::vec2(0.0, self.config.config.controls_on_screen().clearance().0),
+            )
             .pivot(egui::Align2::CENTER_TOP)
             .order(egui::Order::Foreground)
             .show(ctx, |ui| {
@@ -544,15 +2529,118 @@ impl eframe::App for VideoApp {
                     .show(ui, |ui| {
                         ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
                         ui.set_min_width(0.0);
-                        ui.label(
-                            egui::RichText::new(cam_name)
-                                .color(egui::Color32::WHITE)
-                                .strong()
-                                .size(32.0),
-                        )
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                egui::RichText::new(cam_name)
+                                    .color(egui::Color32::WHITE)
+                                    .strong()
+                                    .size(32.0),
+                            );
+
+                            if let Some(network) = network {
+                                let color = match network.quality() {
+                                    NetworkQuality::Good => egui::Color32::from_rgb(80, 200, 120),
+                                    NetworkQuality::Degraded => {
+                                        egui::Color32::from_rgb(240, 180, 40)
+                                    }
+                                    NetworkQuality::Poor => egui::Color32::from_rgb(230, 60, 60),
+                                };
+                                let mut text =
+                                    format!("📶 {:.1} Mb/s", network.bitrate_kbps as f32 / 1000.0);
+                                if network.substream {
+                                    text.push_str(" · SD");
+                                }
+                                if self.dropped_frames > 0 {
+                                    text.push_str(&format!(
+                                        " · {} img. perdues",
+                                        self.dropped_frames
+                                    ));
+                                }
+                                if let Some(latency) = self.latency.summary() {
+                                    text.push_str(&format!(
+                                        " · ⏱ {} ms",
+                                        (latency.decode + latency.display).as_millis()
+                                    ));
+                                }
+                                ui.add_space(12.0);
+                                ui.label(egui::RichText::new(text).color(color).size(20.0));
+                            }
+                        });
                     });
             });
 
+        let camera = &self.config.camera[cam_index];
+        if let Some(reolink) = &camera.reolink {
+            let spotlight_on = self.spotlight_on.contains(&camera.name);
+            let mut toggle_spotlight = false;
+
+            let (overlay_anchor, overlay_offset) = if controls == ControlsPosition::Right {
+                (egui::Align2::LEFT_CENTER, egui::vec2(10.0, 0.0))
+            } else {
+                (egui::Align2::RIGHT_CENTER, egui::vec2(-10.0, 0.0))
+            };
+            egui::Area::new("camera_controls_overlay".into())
+                .anchor(overlay_anchor, overlay_offset)
+                .pivot(overlay_anchor)
+                .order(egui::Order::Foreground)
+                .show(ctx, |ui| {
+                    egui::Frame::new()
+                        .fill(egui::Color32::from_black_alpha(200))
+                        .inner_margin(16.0)
+                        .corner_radius(15.0)
+                        .show(ui, |ui| {
+                            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                            for preset in &camera.ptz_presets {
+                                if ui
+                                    .button(RichText::new(format!("🎯 {}", preset.name)).size(24.))
+                                    .clicked()
+                                {
+                                    ptz::goto_preset(
+                                        camera.name.clone(),
+                                        ReolinkClient::new(reolink.clone()),
+                                        preset.clone(),
+                                    );
+                                }
+                            }
+
+                            if !camera.ptz_presets.is_empty() {
+                                ui.separator();
+                            }
+
+                            let spotlight_label = if spotlight_on {
+                                "💡 Éteindre"
+                            } else {
+                                "💡 Allumer"
+                            };
+                            if ui
+                                .button(RichText::new(spotlight_label).size(24.))
+                                .clicked()
+                            {
+                                toggle_spotlight = true;
+                            }
+                            if ui.button(RichText::new("🚨 Sirène").size(24.)).clicked() {
+                                automation::sound_siren(
+                                    camera.name.clone(),
+                                    ReolinkClient::new(reolink.clone()),
+                                );
+                            }
+                        });
+                });
+
+            if toggle_spotlight {
+                automation::set_spotlight(
+                    camera.name.clone(),
+                    ReolinkClient::new(reolink.clone()),
+                    !spotlight_on,
+                );
+                if spotlight_on {
+                    self.spotlight_on.remove(&camera.name);
+                } else {
+                    self.spotlight_on.insert(camera.name.clone());
+                }
+            }
+        }
+
         if let Some(start) = self.notification_timer {
             let elapsed = start.elapsed().as_secs_f32();
             let flash_duration = 0.15;
@@ -572,117 +2660,74 @@ impl eframe::App for VideoApp {
                 self.notification_timer = None;
             }
         }
-        ctx.request_repaint();
+
+        match self.repaint_interval() {
+            Some(interval) => ctx.request_repaint_after(interval),
+            None => ctx.request_repaint(),
+        }
     }
 }
 
-fn run_decoder_managed(
-    video_stream: VideoStream,
-    has_to_wait_for_keyframe: bool,
-    use_tcp_for_rtsp: bool,
-) -> Result<(), ffmpeg::Error> {
-    let mut running = video_stream.running;
-    let mut waiting_for_keyframe = true;
+fn load_media_texture(ctx: &egui::Context, path: &std::path::Path) -> Option<egui::TextureHandle> {
+    let img = image::open(path).ok()?.to_rgba8();
+    let size = [img.width() as usize, img.height() as usize];
+    let pixels = img.into_raw();
+    let color_image = egui::ColorImage::from_rgba_unmultiplied(size, &pixels);
+    let id = format!("gallery:{}", path.display());
+    Some(ctx.load_texture(&id, color_image, egui::TextureOptions::LINEAR))
+}
 
-    loop {
-        let mut opts = Dictionary::new();
-        if use_tcp_for_rtsp {
-            opts.set("rtsp_transport", "tcp");
+fn date_filter(ui: &mut egui::Ui, label: &str, id: &str, value: &mut Option<chrono::NaiveDate>) {
+    ui.label(label);
+    match value {
+        Some(date) => {
+            ui.add(egui_extras::DatePickerButton::new(date).id_salt(id));
+            if ui.button("✖").clicked() {
+                *value = None;
+            }
         }
-
-        let mut ictx = match ffmpeg::format::input_with_dictionary(&video_stream.url, opts) {
-            Ok(ctx) => ctx,
-            Err(_) => {
-                std::thread::sleep(std::time::Duration::from_secs(5));
-                continue;
+        None => {
+            if ui.button("—").clicked() {
+                *value = Some(chrono::Local::now().date_naive());
             }
-        };
-
-        let input = ictx.streams().best(ffmpeg::media::Type::Video).unwrap();
-        let video_index = input.index();
-        let params = input.parameters();
-        let codec_id = params.id();
+        }
+    }
+}
 
-        let hw_codec_name = match codec_id {
-            ffmpeg::codec::Id::H264 => Some("h264_v4l2m2m"),
-            ffmpeg::codec::Id::HEVC => Some("hevc_v4l2m2m"),
-            ffmpeg::codec::Id::VP8 => Some("vp8_v4l2m2m"),
-            ffmpeg::codec::Id::VP9 => Some("vp9_v4l2m2m"),
-            _ => None,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_button_of_the_toast_can_be_clicked() {
+        let ctx = egui::Context::default();
+        let toast = Toast::undoable("1 média(s) mis à la corbeille".to_string());
+        let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(1280.0, 800.0));
+        let frame = |events: Vec<egui::Event>| {
+            let input = egui::RawInput {
+                screen_rect: Some(screen),
+                events,
+                ..Default::default()
+            };
+            let mut clicked = false;
+            let _ = ctx.run(input, |ctx| {
+                clicked = toast.show(ctx, ControlsPosition::default());
+            });
+            clicked
         };
 
-        let mut decoder = if let Some(name) = hw_codec_name {
-            if let Some(hw_codec) = ffmpeg::decoder::find_by_name(name) {
-                match ffmpeg::codec::context::Context::from_parameters(params.clone())?
-                    .decoder()
-                    .open_as(hw_codec)
-                    .and_then(|c| c.video())
-                {
-                    Ok(hw_dec) => {
-                        println!("Accélération matérielle activée : {}", name);
-                        hw_dec
-                    }
-                    Err(_) => {
-                        println!("Échec matériel pour {}, repli logiciel...", name);
-                        ffmpeg::codec::context::Context::from_parameters(params)?
-                            .decoder()
-                            .video()?
-                    }
-                }
-            } else {
-                println!("Codec HW {} non compilé dans FFmpeg, usage logiciel.", name);
-                ffmpeg::codec::context::Context::from_parameters(params)?
-                    .decoder()
-                    .video()?
-            }
-        } else {
-            println!("Pas de support matériel pour ce format, usage logiciel.");
-            ffmpeg::codec::context::Context::from_parameters(params)?
-                .decoder()
-                .video()?
+        frame(Vec::new());
+        frame(Vec::new());
+        let area = ctx.memory(|memory| memory.area_rect("toast")).unwrap();
+        let button = egui::pos2(area.right() - 40.0, area.center().y);
+        let click = |pressed| egui::Event::PointerButton {
+            pos: button,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::default(),
         };
 
-        let mut scaler = ffmpeg::software::scaling::context::Context::get(
-            decoder.format(),
-            decoder.width(),
-            decoder.height(),
-            ffmpeg::format::Pixel::RGBA,
-            WIDTH,
-            HEIGHT,
-            ffmpeg::software::scaling::flag::Flags::BILINEAR,
-        )?;
-
-        let mut frame = ffmpeg::util::frame::video::Video::empty();
-        let mut frame_rgba = ffmpeg::util::frame::video::Video::empty();
-
-        for (stream, packet) in ictx.packets() {
-            if let Ok(value) = video_stream.stop_receiver.try_recv() {
-                if value && !running {
-                    waiting_for_keyframe = true;
-                }
-                running = value;
-            }
-
-            if stream.index() == video_index && running {
-                if has_to_wait_for_keyframe && waiting_for_keyframe {
-                    if !packet.is_key() {
-                        continue;
-                    } else {
-                        waiting_for_keyframe = false;
-                    }
-                }
-
-                if decoder.send_packet(&packet).is_ok() {
-                    while decoder.receive_frame(&mut frame).is_ok() {
-                        let _ = scaler.run(&frame, &mut frame_rgba);
-
-                        let _ = video_stream.packet_sender.try_send(VideoFrame {
-                            data: frame_rgba.data(0).to_vec(),
-                            url: video_stream.url.clone(),
-                        });
-                    }
-                }
-            }
-        }
+        assert!(!frame(vec![egui::Event::PointerMoved(button), click(true)]));
+        assert!(frame(vec![click(false)]));
     }
 }
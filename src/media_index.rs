@@ -0,0 +1,430 @@
+use crate::event::EventKind;
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const LEGACY_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Snapshot,
+    Clip,
+}
+
+impl MediaKind {
+    fn name(self) -> &'static str {
+        match self {
+            MediaKind::Snapshot => "snapshot",
+            MediaKind::Clip => "clip",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "snapshot" => Some(MediaKind::Snapshot),
+            "clip" => Some(MediaKind::Clip),
+            _ => None,
+        }
+    }
+}
+
+pub struct EventRecord {
+    pub timestamp: NaiveDateTime,
+    pub camera: String,
+    pub event: EventKind,
+}
+
+#[derive(Clone)]
+pub struct MediaRecord {
+    pub timestamp: NaiveDateTime,
+    pub camera: String,
+    pub kind: MediaKind,
+    pub path: PathBuf,
+    pub event: Option<EventKind>,
+}
+
+#[derive(Default, Clone, PartialEq)]
+pub struct MediaFilter {
+    pub camera: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub event: Option<EventKind>,
+}
+
+pub fn sanitize_camera_name(name: &str) -> String {
+    name.replace("://", "_").replace("/", "_").replace(".", "_")
+}
+
+pub fn group_by_day(items: &[MediaRecord]) -> Vec<(NaiveDate, Vec<usize>)> {
+    let mut groups: Vec<(NaiveDate, Vec<usize>)> = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        let day = item.timestamp.date();
+        match groups.last_mut() {
+            Some((last, indices)) if *last == day => indices.push(index),
+            _ => groups.push((day, vec![index])),
+        }
+    }
+    groups
+}
+
+#[derive(Clone)]
+pub struct MediaIndex {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl MediaIndex {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS events (
+                id INTEGER PRIMARY KEY,
+                timestamp TEXT NOT NULL,
+                camera TEXT NOT NULL,
+                kind TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS media (
+                id INTEGER PRIMARY KEY,
+                timestamp TEXT NOT NULL,
+                camera TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                path TEXT NOT NULL UNIQUE,
+                event TEXT
+            );
+            CREATE INDEX IF NOT EXISTS events_timestamp ON events (timestamp);
+            CREATE INDEX IF NOT EXISTS media_timestamp ON media (timestamp);",
+        )?;
+
+        let has_details = conn
+            .prepare("SELECT 1 FROM pragma_table_info('events') WHERE name = 'details'")?
+            .exists([])?;
+        if !has_details {
+            conn.execute("ALTER TABLE events ADD COLUMN details TEXT", [])?;
+        }
+
+        Ok(MediaIndex {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    pub fn record_event(&self, camera: &str, event: EventKind, details: Option<&str>) {
+        let timestamp = chrono::Local::now().naive_local();
+        let conn = self.conn.lock().unwrap();
+        if let Err(e) = conn.execute(
+            "INSERT INTO events (timestamp, camera, kind, details) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                timestamp.format(TIMESTAMP_FORMAT).to_string(),
+                camera,
+                event.name(),
+                details
+            ],
+        ) {
+            eprintln!("Erreur lors de l'enregistrement de l'évènement : {}", e);
+        }
+    }
+
+    pub fn record_media(
+        &self,
+        timestamp: NaiveDateTime,
+        camera: &str,
+        kind: MediaKind,
+        path: &Path,
+        event: Option<EventKind>,
+    ) {
+        let conn = self.conn.lock().unwrap();
+        if let Err(e) = conn.execute(
+            "INSERT OR IGNORE INTO media (timestamp, camera, kind, path, event)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                timestamp.format(TIMESTAMP_FORMAT).to_string(),
+                camera,
+                kind.name(),
+                path.to_string_lossy(),
+                event.map(EventKind::name)
+            ],
+        ) {
+            eprintln!("Erreur lors de l'indexation de {} : {}", path.display(), e);
+        }
+    }
+
+    pub fn import_event_log(&self, path: &Path) {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return;
+        };
+
+        let records: Vec<(NaiveDateTime, &str, EventKind)> = content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, ';');
+                let timestamp =
+                    NaiveDateTime::parse_from_str(fields.next()?, LEGACY_TIMESTAMP_FORMAT).ok()?;
+                let camera = fields.next()?;
+                let event = EventKind::from_name(fields.next()?)?;
+                Some((timestamp, camera, event))
+            })
+            .collect();
+
+        let result = {
+            let mut conn = self.conn.lock().unwrap();
+            conn.transaction().and_then(|tx| {
+                for (timestamp, camera, event) in &records {
+                    tx.execute(
+                        "INSERT INTO events (timestamp, camera, kind) VALUES (?1, ?2, ?3)",
+                        params![
+                            timestamp.format(TIMESTAMP_FORMAT).to_string(),
+                            camera,
+                            event.name()
+                        ],
+                    )?;
+                }
+                tx.commit()
+            })
+        };
+        if let Err(e) = result {
+            eprintln!("Erreur lors de l'import de {} : {}", path.display(), e);
+            return;
+        }
+
+        if let Err(e) = std::fs::rename(path, path.with_extension("csv.importe")) {
+            eprintln!(
+                "Impossible de renommer {} après l'import : {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    pub fn index_existing_snapshots(&self, dir: &Path, cameras: &[String]) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+
+        for path in entries.filter_map(|e| e.ok().map(|d| d.path())) {
+            let is_image = path.extension().is_some_and(|ext| {
+                matches!(
+                    ext.to_string_lossy().to_lowercase().as_str(),
+                    "png" | "jpg" | "jpeg"
+                )
+            });
+            if !is_image || self.contains_media(&path) {
+                continue;
+            }
+
+            let Some(stem) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                continue;
+            };
+            let Some(timestamp) = stem
+                .get(..19)
+                .and_then(|t| NaiveDateTime::parse_from_str(t, "%Y-%m-%d_%H-%M-%S").ok())
+            else {
+                continue;
+            };
+
+            let suffix = stem.get(20..).unwrap_or_default();
+            let camera = cameras
+                .iter()
+                .find(|name| sanitize_camera_name(name) == suffix)
+                .cloned()
+                .unwrap_or_else(|| suffix.to_string());
+
+            self.record_media(timestamp, &camera, MediaKind::Snapshot, &path, None);
+        }
+    }
+
+    fn contains_media(&self, path: &Path) -> bool {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT 1 FROM media WHERE path = ?1",
+            params![path.to_string_lossy()],
+            |_| Ok(()),
+        )
+        .optional()
+        .ok()
+        .flatten()
+        .is_some()
+    }
+
+    pub fn remove_media(&self, path: &Path) {
+        let conn = self.conn.lock().unwrap();
+        if let Err(e) = conn.execute(
+            "DELETE FROM media WHERE path = ?1",
+            params![path.to_string_lossy()],
+        ) {
+            eprintln!(
+                "Erreur lors de la désindexation de {} : {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    pub fn remove_missing_media(&self) {
+        let paths: Vec<String> = {
+            let conn = self.conn.lock().unwrap();
+            let Ok(mut stmt) = conn.prepare("SELECT path FROM media") else {
+                return;
+            };
+            stmt.query_map([], |row| row.get(0))
+                .map(|rows| rows.filter_map(Result::ok).collect())
+                .unwrap_or_default()
+        };
+
+        let missing: Vec<String> = paths
+            .into_iter()
+            .filter(|path| !Path::new(path).exists())
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+
+        let mut conn = self.conn.lock().unwrap();
+        let result = conn.transaction().and_then(|tx| {
+            for path in &missing {
+                tx.execute("DELETE FROM media WHERE path = ?1", params![path])?;
+            }
+            tx.commit()
+        });
+        if let Err(e) = result {
+            eprintln!("Erreur lors du nettoyage de l'index des médias : {}", e);
+        }
+    }
+
+    pub fn events(&self) -> Vec<EventRecord> {
+        let conn = self.conn.lock().unwrap();
+        let Ok(mut stmt) = conn.prepare("SELECT timestamp, camera, kind FROM events") else {
+            return Vec::new();
+        };
+
+        stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map(|rows| {
+            rows.filter_map(Result::ok)
+                .filter_map(|(timestamp, camera, kind)| {
+                    Some(EventRecord {
+                        timestamp: NaiveDateTime::parse_from_str(&timestamp, TIMESTAMP_FORMAT)
+                            .ok()?,
+                        camera,
+                        event: EventKind::from_name(&kind)?,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn media(&self, filter: &MediaFilter) -> Vec<MediaRecord> {
+        let mut sql =
+            "SELECT timestamp, camera, kind, path, event FROM media WHERE 1 = 1".to_string();
+        let mut values: Vec<String> = Vec::new();
+
+        if let Some(camera) = &filter.camera {
+            values.push(camera.clone());
+            sql.push_str(&format!(" AND camera = ?{}", values.len()));
+        }
+        if let Some(from) = filter.from {
+            values.push(from.format("%Y-%m-%d").to_string());
+            sql.push_str(&format!(" AND date(timestamp) >= ?{}", values.len()));
+        }
+        if let Some(to) = filter.to {
+            values.push(to.format("%Y-%m-%d").to_string());
+            sql.push_str(&format!(" AND date(timestamp) <= ?{}", values.len()));
+        }
+        if let Some(event) = filter.event {
+            values.push(event.name().to_string());
+            sql.push_str(&format!(" AND event = ?{}", values.len()));
+        }
+        sql.push_str(" ORDER BY timestamp DESC");
+
+        let conn = self.conn.lock().unwrap();
+        let Ok(mut stmt) = conn.prepare(&sql) else {
+            return Vec::new();
+        };
+
+        stmt.query_map(rusqlite::params_from_iter(values.iter()), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })
+        .map(|rows| {
+            rows.filter_map(Result::ok)
+                .filter_map(|(timestamp, camera, kind, path, event)| {
+                    Some(MediaRecord {
+                        timestamp: NaiveDateTime::parse_from_str(&timestamp, TIMESTAMP_FORMAT)
+                            .ok()?,
+                        camera,
+                        kind: MediaKind::from_name(&kind)?,
+                        path: PathBuf::from(path),
+                        event: event.as_deref().and_then(EventKind::from_name),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_consecutive_media_by_day() {
+        let record = |timestamp: &str, camera: &str| MediaRecord {
+            timestamp: NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).unwrap(),
+            camera: camera.to_string(),
+            kind: MediaKind::Snapshot,
+            path: PathBuf::from(format!("{}.png", timestamp)),
+            event: None,
+        };
+        let items = [
+            record("2025-03-14 18:05:09", "Jardin"),
+            record("2025-03-14 07:00:00", "Portail"),
+            record("2025-03-12 23:59:59", "Jardin"),
+        ];
+
+        let groups = group_by_day(&items);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0, NaiveDate::from_ymd_opt(2025, 3, 14).unwrap());
+        assert_eq!(groups[0].1, vec![0, 1]);
+        assert_eq!(groups[1].1, vec![2]);
+    }
+
+    #[test]
+    fn legacy_event_log_is_imported_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("events.csv");
+        std::fs::write(
+            &log,
+            "2025-03-14T18:05:09;Portail;visitor\n2025-03-14T18:06:00;Jardin;motion\nligne invalide\n",
+        )
+        .unwrap();
+
+        let index = MediaIndex::open_in_memory().unwrap();
+        index.import_event_log(&log);
+        index.import_event_log(&log);
+
+        let events = index.events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].camera, "Portail");
+        assert_eq!(events[0].event, EventKind::Visitor);
+        assert!(!log.exists());
+        assert!(dir.path().join("events.csv.importe").exists());
+    }
+}
@@ -0,0 +1,394 @@
+use ffmpeg_next::Dictionary;
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use ureq::unversioned::resolver::DefaultResolver;
+use ureq::unversioned::transport::{
+    Buffers, ConnectionDetails, Connector, LazyBuffers, NextTimeout, Transport,
+};
+
+const MDNS_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::new(224, 0, 0, 251), 5353);
+const MDNS_TIMEOUT: Duration = Duration::from_secs(2);
+const CACHE_TTL: Duration = Duration::from_secs(300);
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+
+static RESOLVED: LazyLock<Mutex<HashMap<String, (Instant, IpAddr)>>> =
+    LazyLock::new(Default::default);
+static BIND_ADDRESS: OnceLock<IpAddr> = OnceLock::new();
+
+pub fn bind_camera_traffic(address: IpAddr) {
+    if BIND_ADDRESS.set(address).is_ok() {
+        println!("Trafic caméra lié à l'adresse locale {}", address);
+    }
+}
+
+pub fn apply_bind_address(opts: &mut Dictionary) {
+    if let Some(address) = BIND_ADDRESS.get() {
+        opts.set("localaddr", &address.to_string());
+        opts.set("local_addr", &address.to_string());
+    }
+}
+
+pub fn camera_agent(timeout: Duration) -> ureq::Agent {
+    let config = ureq::Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .build();
+    match BIND_ADDRESS.get() {
+        Some(&local) => {
+            ureq::Agent::with_parts(config, BoundConnector { local }, DefaultResolver::default())
+        }
+        None => ureq::Agent::new_with_config(config),
+    }
+}
+
+#[derive(Debug)]
+struct BoundConnector {
+    local: IpAddr,
+}
+
+impl Connector for BoundConnector {
+    type Out = BoundTransport;
+
+    fn connect(
+        &self,
+        details: &ConnectionDetails,
+        _chained: Option<()>,
+    ) -> Result<Option<Self::Out>, ureq::Error> {
+        let mut last_error = None;
+        for addr in details
+            .addrs
+            .iter()
+            .filter(|addr| addr.is_ipv4() == self.local.is_ipv4())
+        {
+            match connect_from(self.local, *addr, details.timeout.not_zero().map(|t| *t)) {
+                Ok(stream) => {
+                    let buffers = LazyBuffers::new(
+                        details.config.input_buffer_size(),
+                        details.config.output_buffer_size(),
+                    );
+                    return Ok(Some(BoundTransport { stream, buffers }));
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::AddrNotAvailable,
+                    format!("aucune adresse joignable depuis {}", self.local),
+                )
+            })
+            .into())
+    }
+}
+
+fn connect_from(
+    local: IpAddr,
+    addr: SocketAddr,
+    timeout: Option<Duration>,
+) -> std::io::Result<TcpStream> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    socket.bind(&SockAddr::from(SocketAddr::new(local, 0)))?;
+    match timeout {
+        Some(timeout) => socket.connect_timeout(&addr.into(), timeout)?,
+        None => socket.connect(&addr.into())?,
+    }
+    socket.set_tcp_nodelay(true)?;
+    Ok(socket.into())
+}
+
+#[derive(Debug)]
+struct BoundTransport {
+    stream: TcpStream,
+    buffers: LazyBuffers,
+}
+
+fn io_error(e: std::io::Error, timeout: &NextTimeout) -> ureq::Error {
+    match e.kind() {
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => {
+            ureq::Error::Timeout(timeout.reason)
+        }
+        _ => e.into(),
+    }
+}
+
+impl Transport for BoundTransport {
+    fn buffers(&mut self) -> &mut dyn Buffers {
+        &mut self.buffers
+    }
+
+    fn transmit_output(&mut self, amount: usize, timeout: NextTimeout) -> Result<(), ureq::Error> {
+        self.stream
+            .set_write_timeout(timeout.not_zero().map(|t| *t))?;
+        let output = &self.buffers.output()[..amount];
+        self.stream
+            .write_all(output)
+            .map_err(|e| io_error(e, &timeout))
+    }
+
+    fn await_input(&mut self, timeout: NextTimeout) -> Result<bool, ureq::Error> {
+        self.stream
+            .set_read_timeout(timeout.not_zero().map(|t| *t))?;
+        let input = self.buffers.input_append_buf();
+        let amount = self.stream.read(input).map_err(|e| io_error(e, &timeout))?;
+        self.buffers.input_appended(amount);
+        Ok(amount > 0)
+    }
+
+    fn is_open(&mut self) -> bool {
+        if self.stream.set_nonblocking(true).is_err() {
+            return false;
+        }
+        let open = matches!(
+            self.stream.read(&mut [0]),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock
+        );
+        open && self.stream.set_nonblocking(false).is_ok()
+    }
+}
+
+fn format_ip(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => ip.to_string(),
+        IpAddr::V6(ip) => format!("[{}]", ip),
+    }
+}
+
+fn split_host_port(authority: &str) -> (&str, Option<&str>) {
+    if let Some(rest) = authority.strip_prefix('[') {
+        let (host, port) = rest.split_once(']').unwrap_or((rest, ""));
+        return (host, port.strip_prefix(':'));
+    }
+    match authority.split_once(':') {
+        Some((host, port)) if !port.contains(':') => (host, Some(port)),
+        Some(_) => (authority, None),
+        None => (authority, None),
+    }
+}
+
+fn resolves_natively(host: &str) -> bool {
+    (host, 0)
+        .to_socket_addrs()
+        .is_ok_and(|mut addrs| addrs.next().is_some())
+}
+
+fn resolve_local(host: &str) -> Option<IpAddr> {
+    let name = host.trim_end_matches('.').to_lowercase();
+    if !name.ends_with(".local") || resolves_natively(host) {
+        return None;
+    }
+
+    if let Some((time, ip)) = RESOLVED.lock().unwrap().get(&name)
+        && time.elapsed() < CACHE_TTL
+    {
+        return Some(*ip);
+    }
+
+    match mdns_query(&name) {
+        Ok(Some(ip)) => {
+            println!("{} résolu par mDNS : {}", name, ip);
+            RESOLVED.lock().unwrap().insert(name, (Instant::now(), ip));
+            Some(ip)
+        }
+        Ok(None) => {
+            eprintln!("Aucune réponse mDNS pour {}", name);
+            None
+        }
+        Err(e) => {
+            eprintln!("Erreur de résolution mDNS de {} : {}", name, e);
+            None
+        }
+    }
+}
+
+pub fn http_host(host: &str) -> String {
+    let (name, port) = split_host_port(host);
+    let address = match name.parse::<Ipv6Addr>() {
+        Ok(ip) => format_ip(IpAddr::V6(ip)),
+        Err(_) => match resolve_local(name) {
+            Some(ip) => format_ip(ip),
+            None => return host.to_string(),
+        },
+    };
+    match port {
+        Some(port) => format!("{}:{}", address, port),
+        None => address,
+    }
+}
+
+pub fn resolve_url(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_len = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_len);
+    let (userinfo, host_port) = match authority.rsplit_once('@') {
+        Some((userinfo, host_port)) => (Some(userinfo), host_port),
+        None => (None, authority),
+    };
+
+    let (name, port) = split_host_port(host_port);
+    let Some(ip) = resolve_local(name) else {
+        return url.to_string();
+    };
+
+    let mut resolved = format!("{}://", scheme);
+    if let Some(userinfo) = userinfo {
+        resolved.push_str(userinfo);
+        resolved.push('@');
+    }
+    resolved.push_str(&format_ip(ip));
+    if let Some(port) = port {
+        resolved.push(':');
+        resolved.push_str(port);
+    }
+    resolved.push_str(path);
+    resolved
+}
+
+fn encode_query(name: &str) -> Vec<u8> {
+    let mut packet = vec![0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0];
+    for record_type in [TYPE_A, TYPE_AAAA] {
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+        packet.extend_from_slice(&record_type.to_be_bytes());
+        packet.extend_from_slice(&1u16.to_be_bytes());
+    }
+    packet
+}
+
+fn read_u16(packet: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *packet.get(offset)?,
+        *packet.get(offset + 1)?,
+    ]))
+}
+
+fn read_name(packet: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+
+    for _ in 0..packet.len() {
+        let len = *packet.get(offset)? as usize;
+        if len == 0 {
+            return Some((labels.join("."), end.unwrap_or(offset + 1)));
+        }
+        if len & 0xC0 == 0xC0 {
+            end.get_or_insert(offset + 2);
+            offset = (read_u16(packet, offset)? & 0x3FFF) as usize;
+            continue;
+        }
+        let label = packet.get(offset + 1..offset + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).to_lowercase());
+        offset += len + 1;
+    }
+    None
+}
+
+fn parse_answer(packet: &[u8], name: &str) -> Option<IpAddr> {
+    let questions = read_u16(packet, 4)?;
+    let answers = read_u16(packet, 6)? + read_u16(packet, 8)? + read_u16(packet, 10)?;
+
+    let mut offset = 12;
+    for _ in 0..questions {
+        offset = read_name(packet, offset)?.1 + 4;
+    }
+
+    let mut ipv6 = None;
+    for _ in 0..answers {
+        let (record_name, next) = read_name(packet, offset)?;
+        let record_type = read_u16(packet, next)?;
+        let data_len = read_u16(packet, next + 8)? as usize;
+        let data = packet.get(next + 10..next + 10 + data_len)?;
+        offset = next + 10 + data_len;
+
+        if record_name != name {
+            continue;
+        }
+        match (record_type, data.len()) {
+            (TYPE_A, 4) => return Some(IpAddr::from([data[0], data[1], data[2], data[3]])),
+            (TYPE_AAAA, 16) => {
+                let octets: [u8; 16] = data.try_into().ok()?;
+                ipv6.get_or_insert(IpAddr::from(octets));
+            }
+            _ => {}
+        }
+    }
+    ipv6
+}
+
+fn mdns_query(name: &str) -> std::io::Result<Option<IpAddr>> {
+    let socket = UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)))?;
+    socket.set_read_timeout(Some(MDNS_TIMEOUT))?;
+    socket.send_to(&encode_query(name), MDNS_ADDR)?;
+
+    let deadline = Instant::now() + MDNS_TIMEOUT;
+    let mut buffer = [0u8; 1500];
+    while Instant::now() < deadline {
+        let len = match socket.recv(&mut buffer) {
+            Ok(len) => len,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        if let Some(ip) = parse_answer(&buffer[..len], name) {
+            return Ok(Some(ip));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_compressed_mdns_answer() {
+        let name = "sonnette.local";
+        let mut response = encode_query(name);
+        response[2] = 0x84;
+        response[7] = 2;
+        for (record_type, data) in [
+            (
+                TYPE_AAAA,
+                vec![0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            ),
+            (TYPE_A, vec![192, 168, 1, 42]),
+        ] {
+            response.extend_from_slice(&[0xC0, 12]);
+            response.extend_from_slice(&record_type.to_be_bytes());
+            response.extend_from_slice(&[0x80, 1, 0, 0, 0, 120]);
+            response.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            response.extend_from_slice(&data);
+        }
+
+        assert_eq!(
+            parse_answer(&response, name),
+            Some(IpAddr::from([192, 168, 1, 42]))
+        );
+    }
+
+    #[test]
+    fn brackets_ipv6_literals() {
+        assert_eq!(http_host("fd00::12"), "[fd00::12]");
+        assert_eq!(http_host("[fd00::12]:8080"), "[fd00::12]:8080");
+        assert_eq!(http_host("192.168.1.10:80"), "192.168.1.10:80");
+        assert_eq!(
+            resolve_url("rtsp://admin:pass@[fd00::12]:554/h264Preview_01_main"),
+            "rtsp://admin:pass@[fd00::12]:554/h264Preview_01_main"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+const MAX_BUFFER_LEN: usize = 256 * 1024;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnalyticsObject {
+    pub class: Option<String>,
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+#[derive(Default)]
+pub struct MetadataParser {
+    buffer: String,
+}
+
+impl MetadataParser {
+    pub fn push(&mut self, data: &[u8]) -> Option<Vec<AnalyticsObject>> {
+        self.buffer.push_str(&String::from_utf8_lossy(data));
+        if self.buffer.len() > MAX_BUFFER_LEN {
+            self.buffer.clear();
+            return None;
+        }
+
+        let mut latest = None;
+        while let Some(end) = find_closing_tag(&self.buffer, "MetadataStream") {
+            let document: String = self.buffer.drain(..end).collect();
+            if let Some(objects) = parse_metadata(&document) {
+                latest = Some(objects);
+            }
+        }
+        latest
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn find_closing_tag(xml: &str, name: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(start) = xml[offset..].find("</") {
+        let tag_start = offset + start + 2;
+        let tag_end = tag_start + xml[tag_start..].find('>')?;
+        if local_name(xml[tag_start..tag_end].trim()) == name {
+            return Some(tag_end + 1);
+        }
+        offset = tag_end;
+    }
+    None
+}
+
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        let element = &rest[start..];
+        let tag_name = element[1..]
+            .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .next()
+            .unwrap_or_default();
+        if tag_name.is_empty() || local_name(tag_name) != name {
+            rest = &element[1..];
+            continue;
+        }
+
+        let Some(start_end) = element.find('>') else {
+            break;
+        };
+        if element[..start_end].ends_with('/') {
+            found.push(&element[..=start_end]);
+            rest = &element[start_end + 1..];
+            continue;
+        }
+
+        let closing = format!("</{}>", tag_name);
+        let Some(close) = element.find(&closing) else {
+            break;
+        };
+        let len = close + closing.len();
+        found.push(&element[..len]);
+        rest = &element[len..];
+    }
+    found
+}
+
+fn attribute(element: &str, name: &str) -> Option<f32> {
+    let start_tag = &element[..element.find('>')?];
+    let pattern = format!(" {}=\"", name);
+    let value_start = start_tag.find(&pattern)? + pattern.len();
+    let value_len = start_tag[value_start..].find('"')?;
+    start_tag[value_start..value_start + value_len].parse().ok()
+}
+
+fn text(element: &str) -> Option<&str> {
+    let content = &element[element.find('>')? + 1..];
+    let content = content[..content.find("</")?].trim();
+    (!content.is_empty()).then_some(content)
+}
+
+fn parse_metadata(document: &str) -> Option<Vec<AnalyticsObject>> {
+    let frame = elements(document, "Frame").into_iter().last()?;
+
+    let (mut translate, mut scale) = ((0.0, 0.0), (1.0, 1.0));
+    if let Some(transformation) = elements(frame, "Transformation").first() {
+        if let Some(element) = elements(transformation, "Translate").first() {
+            translate = (
+                attribute(element, "x").unwrap_or(0.0),
+                attribute(element, "y").unwrap_or(0.0),
+            );
+        }
+        if let Some(element) = elements(transformation, "Scale").first() {
+            scale = (
+                attribute(element, "x").unwrap_or(1.0),
+                attribute(element, "y").unwrap_or(1.0),
+            );
+        }
+    }
+    let to_image = |x: f32, y: f32| {
+        let x = translate.0 + scale.0 * x;
+        let y = translate.1 + scale.1 * y;
+        (
+            ((x + 1.0) / 2.0).clamp(0.0, 1.0),
+            ((1.0 - y) / 2.0).clamp(0.0, 1.0),
+        )
+    };
+
+    let objects = elements(frame, "Object")
+        .into_iter()
+        .filter_map(|object| {
+            let bounds = *elements(object, "BoundingBox").first()?;
+            let (x1, y1) = to_image(attribute(bounds, "left")?, attribute(bounds, "top")?);
+            let (x2, y2) = to_image(attribute(bounds, "right")?, attribute(bounds, "bottom")?);
+            let class = elements(object, "Type")
+                .first()
+                .and_then(|element| text(element))
+                .map(str::to_string);
+
+            Some(AnalyticsObject {
+                class,
+                left: x1.min(x2),
+                top: y1.min(y2),
+                right: x1.max(x2),
+                bottom: y1.max(y2),
+            })
+        })
+        .collect();
+    Some(objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tt:MetadataStream xmlns:tt="http://www.onvif.org/ver10/schema">
+  <tt:VideoAnalytics>
+    <tt:Frame UtcTime="2026-10-16T12:24:57.321Z">
+      <tt:Object ObjectId="12">
+        <tt:Appearance>
+          <tt:Shape>
+            <tt:BoundingBox left="-0.5" top="0.5" right="0.0" bottom="-0.5"/>
+          </tt:Shape>
+          <tt:Class>
+            <tt:Type Likelihood="0.9">Human</tt:Type>
+          </tt:Class>
+        </tt:Appearance>
+      </tt:Object>
+    </tt:Frame>
+  </tt:VideoAnalytics>
+</tt:MetadataStream>"#;
+
+    #[test]
+    fn parses_objects_split_across_packets() {
+        let mut parser = MetadataParser::default();
+        let (first, second) = METADATA.split_at(200);
+
+        assert_eq!(parser.push(first.as_bytes()), None);
+        let objects = parser.push(second.as_bytes()).unwrap();
+
+        assert_eq!(
+            objects,
+            vec![AnalyticsObject {
+                class: Some("Human".to_string()),
+                left: 0.25,
+                top: 0.25,
+                right: 0.5,
+                bottom: 0.75,
+            }]
+        );
+    }
+}
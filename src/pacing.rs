@@ -0,0 +1,160 @@
+use crate::decoder::VideoFrame;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const MAX_DRIFT: Duration = Duration::from_secs(2);
+const MAX_PENDING_FRAMES: usize = 8;
+
+#[derive(Clone, Default)]
+pub struct FrameBudget {
+    pending: Arc<AtomicUsize>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl FrameBudget {
+    pub fn reserve(&self) {
+        self.pending.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn release(&self, count: usize) {
+        let _ = self
+            .pending
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pending| {
+                Some(pending.saturating_sub(count))
+            });
+    }
+
+    pub fn is_saturated(&self) -> bool {
+        self.pending.load(Ordering::Relaxed) >= MAX_PENDING_FRAMES
+    }
+
+    pub fn record_dropped(&self, count: u64) {
+        self.dropped.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn take_dropped(&self) -> u64 {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+pub struct FramePacer {
+    queue: VecDeque<Arc<VideoFrame>>,
+    anchor: Option<(Duration, Instant)>,
+    drop_late_frames: bool,
+    dropped: u64,
+    budget: FrameBudget,
+}
+
+impl FramePacer {
+    pub fn new(drop_late_frames: bool, budget: FrameBudget) -> Self {
+        FramePacer {
+            queue: VecDeque::new(),
+            anchor: None,
+            drop_late_frames,
+            dropped: 0,
+            budget,
+        }
+    }
+
+    pub fn push(&mut self, frame: Arc<VideoFrame>) {
+        self.queue.push_back(frame);
+        while self.queue.len() > MAX_PENDING_FRAMES {
+            self.queue.pop_front();
+            self.budget.release(1);
+            self.dropped += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.budget.release(self.queue.len());
+        self.queue.clear();
+        self.anchor = None;
+    }
+
+    fn is_due(&mut self, frame: &VideoFrame, now: Instant) -> bool {
+        let Some(pts) = frame.pts else {
+            return true;
+        };
+
+        let (anchor_pts, anchor_instant) = *self.anchor.get_or_insert((pts, now));
+        let playback_pts = anchor_pts + now.duration_since(anchor_instant);
+
+        if pts < anchor_pts || pts > playback_pts + MAX_DRIFT {
+            self.anchor = Some((pts, now));
+            return true;
+        }
+
+        pts <= playback_pts
+    }
+
+    pub fn next_due_in(&self, now: Instant) -> Option<Duration> {
+        let frame = self.queue.front()?;
+        let (Some(pts), Some((anchor_pts, anchor_instant))) = (frame.pts, self.anchor) else {
+            return Some(Duration::ZERO);
+        };
+        let wait = pts
+            .checked_sub(anchor_pts)
+            .map(|offset| (anchor_instant + offset).saturating_duration_since(now))
+            .filter(|wait| *wait <= MAX_DRIFT)
+            .unwrap_or_default();
+        Some(wait)
+    }
+
+    pub fn next_frame(&mut self) -> Option<Arc<VideoFrame>> {
+        let now = Instant::now();
+        let mut shown = None;
+
+        while let Some(frame) = self.queue.pop_front() {
+            if !self.is_due(&frame, now) {
+                self.queue.push_front(frame);
+                break;
+            }
+            self.budget.release(1);
+
+            if shown.replace(frame).is_some() {
+                self.dropped += 1;
+            }
+            if !self.drop_late_frames {
+                break;
+            }
+        }
+
+        shown
+    }
+
+    pub fn take_dropped(&mut self) -> u64 {
+        std::mem::take(&mut self.dropped) + self.budget.take_dropped()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_due_in_follows_frame_timestamps() {
+        let frame = |millis| {
+            let now = Instant::now();
+            Arc::new(VideoFrame {
+                data: Vec::new(),
+                url: "rtsp://jardin".to_string(),
+                camera: "Jardin".to_string(),
+                pts: Some(Duration::from_millis(millis)),
+                received_at: now,
+                decoded_at: now,
+            })
+        };
+        let mut pacer = FramePacer::new(false, FrameBudget::default());
+        assert_eq!(pacer.next_due_in(Instant::now()), None);
+
+        pacer.push(frame(1000));
+        pacer.push(frame(1040));
+        assert_eq!(pacer.next_due_in(Instant::now()), Some(Duration::ZERO));
+        assert!(pacer.next_frame().is_some());
+
+        let wait = pacer.next_due_in(Instant::now()).unwrap();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(40));
+    }
+}
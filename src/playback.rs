@@ -0,0 +1,474 @@
+use crate::color::ColorConfig;
+use crate::display::fit_rect;
+use crate::media_index::{MediaKind, MediaRecord};
+use chrono::NaiveDateTime;
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use eframe::egui::{self, RichText};
+use ffmpeg_next::{self as ffmpeg};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MAX_CAMERAS: usize = 4;
+const FRAME_WIDTH: u32 = 640;
+const FRAME_HEIGHT: u32 = 360;
+const MAX_DECODE_AHEAD: Duration = Duration::from_secs(3);
+const FRAME_TOLERANCE: Duration = Duration::from_millis(100);
+
+#[derive(Clone)]
+struct Clip {
+    start: NaiveDateTime,
+    duration: Duration,
+    path: PathBuf,
+}
+
+impl Clip {
+    fn end(&self) -> NaiveDateTime {
+        self.start + chrono::Duration::from_std(self.duration).unwrap_or_default()
+    }
+
+    fn contains(&self, time: NaiveDateTime) -> bool {
+        self.start <= time && time < self.end()
+    }
+}
+
+struct ClipDecoder {
+    path: PathBuf,
+    input: ffmpeg::format::context::Input,
+    decoder: ffmpeg::decoder::Video,
+    stream_index: usize,
+    time_base: ffmpeg::Rational,
+    start_pts: i64,
+    position: Duration,
+    last: Option<Vec<u8>>,
+    scaler: Option<ffmpeg::software::scaling::Context>,
+}
+
+impl ClipDecoder {
+    fn open(path: &Path) -> Result<Self, ffmpeg::Error> {
+        let input = ffmpeg::format::input(path)?;
+        let stream = input
+            .streams()
+            .best(ffmpeg::media::Type::Video)
+            .ok_or(ffmpeg::Error::StreamNotFound)?;
+        let stream_index = stream.index();
+        let time_base = stream.time_base();
+        let start_pts = stream.start_time().max(0);
+        let decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?
+            .decoder()
+            .video()?;
+
+        Ok(ClipDecoder {
+            path: path.to_path_buf(),
+            input,
+            decoder,
+            stream_index,
+            time_base,
+            start_pts,
+            position: Duration::ZERO,
+            last: None,
+            scaler: None,
+        })
+    }
+
+    fn frame_at(&mut self, offset: Duration) -> Result<Option<Vec<u8>>, ffmpeg::Error> {
+        if self.last.is_some()
+            && offset <= self.position
+            && self.position - offset < FRAME_TOLERANCE
+        {
+            return Ok(self.last.clone());
+        }
+
+        if offset < self.position || offset > self.position + MAX_DECODE_AHEAD {
+            let timestamp = offset.as_micros() as i64;
+            self.input.seek(timestamp, ..timestamp)?;
+            self.decoder.flush();
+            self.position = Duration::ZERO;
+        }
+
+        let mut frame = ffmpeg::util::frame::video::Video::empty();
+        loop {
+            while self.decoder.receive_frame(&mut frame).is_ok() {
+                if let Some(pts) = frame.timestamp()
+                    && self.time_base.denominator() != 0
+                {
+                    let pts = (pts - self.start_pts).max(0);
+                    self.position = Duration::from_secs_f64(pts as f64 * f64::from(self.time_base));
+                }
+                if self.position + FRAME_TOLERANCE >= offset {
+                    self.last = Some(self.convert(&frame)?);
+                    return Ok(self.last.clone());
+                }
+            }
+
+            let Some((stream, packet)) = self.input.packets().next() else {
+                return Ok(self.last.clone());
+            };
+            if stream.index() == self.stream_index {
+                self.decoder.send_packet(&packet)?;
+            }
+        }
+    }
+
+    fn convert(
+        &mut self,
+        frame: &ffmpeg::util::frame::video::Video,
+    ) -> Result<Vec<u8>, ffmpeg::Error> {
+        let color = ColorConfig::default();
+        let outdated = self.scaler.as_ref().is_none_or(|scaler| {
+            let input = scaler.input();
+            input.format != frame.format()
+                || input.width != frame.width()
+                || input.height != frame.height()
+        });
+        if outdated {
+            let mut scaler = ffmpeg::software::scaling::Context::get(
+                frame.format(),
+                frame.width(),
+                frame.height(),
+                ffmpeg::format::Pixel::RGBA,
+                FRAME_WIDTH,
+                FRAME_HEIGHT,
+                ffmpeg::software::scaling::Flags::BILINEAR,
+            )?;
+            color.configure(&mut scaler, frame);
+            self.scaler = Some(scaler);
+        }
+
+        let mut rgba = ffmpeg::util::frame::video::Video::empty();
+        if let Some(scaler) = &mut self.scaler {
+            scaler.run(frame, &mut rgba)?;
+        }
+
+        let stride = rgba.stride(0);
+        let row = FRAME_WIDTH as usize * 4;
+        let mut data = Vec::with_capacity(row * FRAME_HEIGHT as usize);
+        for line in rgba.data(0).chunks(stride).take(FRAME_HEIGHT as usize) {
+            data.extend_from_slice(&line[..row]);
+        }
+        if let Some(tone_mapper) = color.tone_mapper(frame) {
+            tone_mapper.apply(&mut data);
+        }
+        Ok(data)
+    }
+}
+
+fn run_player(
+    clips: Vec<Clip>,
+    requests: Receiver<NaiveDateTime>,
+    frames: Sender<Option<Vec<u8>>>,
+) {
+    let mut current: Option<ClipDecoder> = None;
+
+    while let Ok(mut time) = requests.recv() {
+        while let Ok(next) = requests.try_recv() {
+            time = next;
+        }
+
+        let Some(clip) = clips.iter().find(|clip| clip.contains(time)) else {
+            current = None;
+            if frames.send(None).is_err() {
+                return;
+            }
+            continue;
+        };
+
+        if current
+            .as_ref()
+            .is_none_or(|decoder| decoder.path != clip.path)
+        {
+            current = ClipDecoder::open(&clip.path)
+                .map_err(|e| eprintln!("Impossible d'ouvrir {} : {}", clip.path.display(), e))
+                .ok();
+        }
+
+        let offset = (time - clip.start).to_std().unwrap_or_default();
+        let frame = current.as_mut().and_then(|decoder| {
+            decoder
+                .frame_at(offset)
+                .map_err(|e| eprintln!("Erreur de lecture de {} : {}", clip.path.display(), e))
+                .ok()
+                .flatten()
+        });
+        if frames.send(frame).is_err() {
+            return;
+        }
+    }
+}
+
+fn clip_duration(path: &Path) -> Option<Duration> {
+    let input = ffmpeg::format::input(path).ok()?;
+    let micros = u64::try_from(input.duration()).ok()?;
+    (micros > 0).then(|| Duration::from_micros(micros))
+}
+
+struct CameraPlayer {
+    camera: String,
+    requests: Sender<NaiveDateTime>,
+    frames: Receiver<Option<Vec<u8>>>,
+    texture: Option<egui::TextureHandle>,
+    no_clip: bool,
+}
+
+impl CameraPlayer {
+    fn spawn(camera: &str, clips: Vec<Clip>) -> Self {
+        let (requests, request_receiver) = unbounded();
+        let (frame_sender, frames) = unbounded();
+        thread::spawn(move || run_player(clips, request_receiver, frame_sender));
+
+        CameraPlayer {
+            camera: camera.to_string(),
+            requests,
+            frames,
+            texture: None,
+            no_clip: false,
+        }
+    }
+}
+
+pub struct SyncPlayback {
+    clips: BTreeMap<String, Vec<Clip>>,
+    players: Vec<CameraPlayer>,
+    position: NaiveDateTime,
+    playing: Option<(Instant, NaiveDateTime)>,
+    requested: Option<NaiveDateTime>,
+}
+
+impl SyncPlayback {
+    pub fn new(records: &[MediaRecord]) -> Self {
+        let mut clips: BTreeMap<String, Vec<Clip>> = BTreeMap::new();
+        for record in records {
+            if record.kind != MediaKind::Clip {
+                continue;
+            }
+            let Some(duration) = clip_duration(&record.path) else {
+                continue;
+            };
+            clips.entry(record.camera.clone()).or_default().push(Clip {
+                start: record.timestamp,
+                duration,
+                path: record.path.clone(),
+            });
+        }
+        for camera_clips in clips.values_mut() {
+            camera_clips.sort_by_key(|clip| clip.start);
+        }
+
+        let mut playback = SyncPlayback {
+            clips,
+            players: Vec::new(),
+            position: NaiveDateTime::default(),
+            playing: None,
+            requested: None,
+        };
+
+        let cameras: Vec<String> = playback.clips.keys().take(2).cloned().collect();
+        for camera in cameras {
+            playback.toggle_camera(&camera);
+        }
+        if let Some((start, _)) = playback.range() {
+            playback.position = start;
+        }
+        playback
+    }
+
+    fn toggle_camera(&mut self, camera: &str) {
+        if let Some(index) = self.players.iter().position(|p| p.camera == camera) {
+            self.players.remove(index);
+        } else if self.players.len() < MAX_CAMERAS
+            && let Some(clips) = self.clips.get(camera)
+        {
+            self.players
+                .push(CameraPlayer::spawn(camera, clips.clone()));
+            self.requested = None;
+        }
+    }
+
+    fn selected_clips(&self) -> impl Iterator<Item = &Clip> {
+        self.players
+            .iter()
+            .filter_map(|player| self.clips.get(&player.camera))
+            .flatten()
+    }
+
+    fn range(&self) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        let start = self.selected_clips().map(|clip| clip.start).min()?;
+        let end = self.selected_clips().map(Clip::end).max()?;
+        Some((start, end))
+    }
+
+    fn jump(&mut self, forward: bool) {
+        let position = self.position;
+        let target = if forward {
+            self.selected_clips()
+                .map(|clip| clip.start)
+                .filter(|start| *start > position)
+                .min()
+        } else {
+            self.selected_clips()
+                .map(|clip| clip.start)
+                .filter(|start| *start < position - chrono::Duration::seconds(1))
+                .max()
+        };
+        if let Some(target) = target {
+            self.seek(target);
+        }
+    }
+
+    fn seek(&mut self, time: NaiveDateTime) {
+        self.position = time;
+        if self.playing.is_some() {
+            self.playing = Some((Instant::now(), time));
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context) {
+        if let Some((anchor_instant, anchor_time)) = self.playing {
+            let elapsed = chrono::Duration::from_std(anchor_instant.elapsed()).unwrap_or_default();
+            self.position = anchor_time + elapsed;
+            if let Some((_, end)) = self.range()
+                && self.position >= end
+            {
+                self.position = end;
+                self.playing = None;
+            }
+            ctx.request_repaint();
+        }
+
+        if self.requested != Some(self.position) {
+            for player in &self.players {
+                let _ = player.requests.send(self.position);
+            }
+            self.requested = Some(self.position);
+        }
+
+        for player in &mut self.players {
+            if let Some(frame) = player.frames.try_iter().last() {
+                player.no_clip = frame.is_none();
+                if let Some(data) = frame {
+                    let image = egui::ColorImage::from_rgba_unmultiplied(
+                        [FRAME_WIDTH as usize, FRAME_HEIGHT as usize],
+                        &data,
+                    );
+                    player.texture = Some(ctx.load_texture(
+                        format!("playback:{}", player.camera),
+                        image,
+                        egui::TextureOptions::LINEAR,
+                    ));
+                }
+            }
+        }
+        if !self.players.is_empty() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        self.update(ui.ctx());
+
+        if self.clips.is_empty() {
+            ui.centered_and_justified(|ui| {
+                ui.label(RichText::new("Aucune vidéo enregistrée pour cette période...").size(32.));
+            });
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 16.0;
+            ui.style_mut().override_font_id = Some(egui::FontId::proportional(24.0));
+
+            let cameras: Vec<String> = self.clips.keys().cloned().collect();
+            for camera in cameras {
+                let mut selected = self.players.iter().any(|p| p.camera == camera);
+                let full = !selected && self.players.len() >= MAX_CAMERAS;
+                if ui
+                    .add_enabled(!full, egui::Checkbox::new(&mut selected, camera.as_str()))
+                    .changed()
+                {
+                    self.toggle_camera(&camera);
+                }
+            }
+            if self.players.len() < 2 {
+                ui.label(RichText::new("Sélectionnez 2 à 4 caméras").color(egui::Color32::GRAY));
+            }
+        });
+
+        let Some((start, end)) = self.range() else {
+            return;
+        };
+        self.position = self.position.clamp(start, end);
+
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 16.0;
+            ui.style_mut().override_font_id = Some(egui::FontId::proportional(24.0));
+
+            if ui.button("⏮").clicked() {
+                self.jump(false);
+            }
+            let play_label = if self.playing.is_some() { "⏸" } else { "▶" };
+            if ui.button(play_label).clicked() {
+                self.playing = match self.playing {
+                    Some(_) => None,
+                    None => Some((Instant::now(), self.position)),
+                };
+            }
+            if ui.button("⏭").clicked() {
+                self.jump(true);
+            }
+
+            let total = (end - start).num_milliseconds() as f64 / 1000.0;
+            let mut offset = (self.position - start).num_milliseconds() as f64 / 1000.0;
+            ui.spacing_mut().slider_width = (ui.available_width() - 160.0).max(100.0);
+            let slider = ui.add(egui::Slider::new(&mut offset, 0.0..=total).show_value(false));
+            if slider.changed() {
+                self.seek(start + chrono::Duration::milliseconds((offset * 1000.0) as i64));
+            }
+            ui.label(self.position.format("%d/%m %H:%M:%S").to_string());
+        });
+
+        let available = ui.available_rect_before_wrap();
+        let columns = if self.players.len() > 1 { 2 } else { 1 };
+        let rows = self.players.len().div_ceil(columns).max(1);
+        let cell_size = egui::vec2(
+            available.width() / columns as f32,
+            available.height() / rows as f32,
+        );
+
+        let painter = ui.painter();
+        for (index, player) in self.players.iter().enumerate() {
+            let min = available.min
+                + egui::vec2(
+                    (index % columns) as f32 * cell_size.x,
+                    (index / columns) as f32 * cell_size.y,
+                );
+            let cell = egui::Rect::from_min_size(min, cell_size).shrink(4.0);
+
+            match (&player.texture, player.no_clip) {
+                (Some(texture), false) => {
+                    let rect = fit_rect(cell, texture.size_vec2());
+                    let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                    painter.image(texture.id(), rect, uv, egui::Color32::WHITE);
+                }
+                _ => {
+                    painter.rect_filled(cell, 0.0, egui::Color32::from_gray(15));
+                    painter.text(
+                        cell.center(),
+                        egui::Align2::CENTER_CENTER,
+                        "Aucun enregistrement",
+                        egui::FontId::proportional(24.0),
+                        egui::Color32::GRAY,
+                    );
+                }
+            }
+
+            painter.text(
+                cell.left_top() + egui::vec2(10.0, 8.0),
+                egui::Align2::LEFT_TOP,
+                &player.camera,
+                egui::FontId::proportional(22.0),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::bus::{CameraEvent, EventBus};
+use crate::event::RemoteCommand;
+use chrono::NaiveTime;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::UdpSocket;
+use std::thread;
+use std::time::Duration;
+
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PowerConfig {
+    pub wol_mac: Option<String>,
+    #[serde(default = "default_wol_broadcast")]
+    pub wol_broadcast: String,
+    pub on_url: Option<String>,
+    pub off_url: Option<String>,
+    pub on: Option<NaiveTime>,
+    pub off: Option<NaiveTime>,
+}
+
+fn default_wol_broadcast() -> String {
+    "255.255.255.255:9".to_string()
+}
+
+impl PowerConfig {
+    fn scheduled_state(&self, time: NaiveTime) -> Option<bool> {
+        let (on, off) = (self.on?, self.off?);
+        Some(if on <= off {
+            time >= on && time < off
+        } else {
+            time >= on || time < off
+        })
+    }
+}
+
+fn parse_mac(mac: &str) -> Option<[u8; 6]> {
+    let bytes: Vec<u8> = mac
+        .split([':', '-'])
+        .map(|part| u8::from_str_radix(part, 16).ok())
+        .collect::<Option<_>>()?;
+    bytes.try_into().ok()
+}
+
+fn magic_packet(mac: [u8; 6]) -> Vec<u8> {
+    let mut packet = vec![0xFF; 6];
+    for _ in 0..16 {
+        packet.extend_from_slice(&mac);
+    }
+    packet
+}
+
+fn send_wol(mac: &str, broadcast: &str) -> Result<(), String> {
+    let mac = parse_mac(mac).ok_or_else(|| format!("adresse MAC invalide : {}", mac))?;
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+    socket.set_broadcast(true).map_err(|e| e.to_string())?;
+    socket
+        .send_to(&magic_packet(mac), broadcast)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn call_switch(url: &str) -> Result<(), String> {
+    let agent = ureq::Agent::new_with_config(
+        ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(5)))
+            .build(),
+    );
+    agent.get(url).call().map(|_| ()).map_err(|e| e.to_string())
+}
+
+pub fn set_power(camera: String, config: PowerConfig, on: bool) {
+    thread::spawn(move || {
+        let url = if on { &config.on_url } else { &config.off_url };
+        if let Some(url) = url
+            && let Err(e) = call_switch(url)
+        {
+            eprintln!("Impossible de piloter l'alimentation de {} : {}", camera, e);
+        }
+
+        if on
+            && let Some(mac) = &config.wol_mac
+            && let Err(e) = send_wol(mac, &config.wol_broadcast)
+        {
+            eprintln!("Impossible de réveiller {} par le réseau : {}", camera, e);
+        }
+    });
+}
+
+pub fn spawn_schedule(cameras: Vec<(String, PowerConfig)>, bus: EventBus) {
+    let cameras: Vec<_> = cameras
+        .into_iter()
+        .filter(|(_, config)| config.on.is_some() && config.off.is_some())
+        .collect();
+    if cameras.is_empty() {
+        return;
+    }
+
+    thread::spawn(move || {
+        let mut applied: HashMap<String, bool> = HashMap::new();
+        loop {
+            let now = chrono::Local::now().time();
+            for (camera, config) in &cameras {
+                let Some(on) = config.scheduled_state(now) else {
+                    continue;
+                };
+                if applied.insert(camera.clone(), on) != Some(on) {
+                    bus.publish(CameraEvent::Command(RemoteCommand::CameraPower {
+                        camera: camera.clone(),
+                        on,
+                    }));
+                }
+            }
+            thread::sleep(SCHEDULE_CHECK_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_magic_packet_from_mac() {
+        let mac = parse_mac("ec:71:DB:01:02:03").unwrap();
+        assert_eq!(mac, [0xEC, 0x71, 0xDB, 0x01, 0x02, 0x03]);
+        assert!(parse_mac("ec-71-db-01-02").is_none());
+
+        let packet = magic_packet(mac);
+        assert_eq!(packet.len(), 102);
+        assert_eq!(&packet[..6], &[0xFF; 6]);
+        assert_eq!(&packet[96..], &mac);
+    }
+
+    #[test]
+    fn schedule_wraps_around_midnight() {
+        let config = PowerConfig {
+            wol_mac: None,
+            wol_broadcast: default_wol_broadcast(),
+            on_url: None,
+            off_url: None,
+            on: NaiveTime::from_hms_opt(7, 0, 0),
+            off: NaiveTime::from_hms_opt(1, 0, 0),
+        };
+        let at = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        assert_eq!(config.scheduled_state(at(12)), Some(true));
+        assert_eq!(config.scheduled_state(at(0)), Some(true));
+        assert_eq!(config.scheduled_state(at(3)), Some(false));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::automation::{RuleAction, RuleConfig};
+use crate::bus::{CameraEvent, EventBus};
+use crate::event::EventKind;
+use serde::Deserialize;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const ARP_TABLE: &str = "/proc/net/arp";
+const ROUTE_TABLE: &str = "/proc/net/route";
+const ARP_COMPLETE: u32 = 0x2;
+const MIN_PROBED_PREFIX: u32 = 22;
+const PROBE_WAIT: Duration = Duration::from_secs(2);
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Occupancy {
+    Home,
+    Away,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PresenceConfig {
+    pub devices: Vec<String>,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_away_after_secs")]
+    pub away_after_secs: u64,
+    #[serde(default = "default_away_record_secs")]
+    pub away_record_secs: u64,
+}
+
+fn default_interval_secs() -> u64 {
+    60
+}
+
+fn default_away_after_secs() -> u64 {
+    900
+}
+
+fn default_away_record_secs() -> u64 {
+    30
+}
+
+impl PresenceConfig {
+    pub fn away_rules(&self) -> Vec<RuleConfig> {
+        if self.away_record_secs == 0 {
+            return Vec::new();
+        }
+        [
+            EventKind::Visitor,
+            EventKind::Person,
+            EventKind::Motion,
+            EventKind::LoudNoise,
+        ]
+        .into_iter()
+        .map(|event| RuleConfig {
+            event,
+            camera: None,
+            start: None,
+            end: None,
+            presence: Some(Occupancy::Away),
+            actions: vec![RuleAction::Record {
+                duration_secs: self.away_record_secs,
+            }],
+        })
+        .collect()
+    }
+}
+
+fn parse_arp_table(table: &str) -> Vec<(String, String)> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let flags = u32::from_str_radix(fields.get(2)?.trim_start_matches("0x"), 16).ok()?;
+            let mac = fields.get(3)?.to_lowercase();
+            (flags & ARP_COMPLETE != 0).then(|| (fields[0].to_string(), mac))
+        })
+        .collect()
+}
+
+fn parse_local_subnets(table: &str) -> Vec<(Ipv4Addr, u32)> {
+    let hex = |field: &str| u32::from_str_radix(field, 16).ok();
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let destination = hex(fields.get(1)?)?;
+            let gateway = hex(fields.get(2)?)?;
+            let mask = hex(fields.get(7)?)?;
+            let prefix = mask.count_ones();
+            (destination != 0 && gateway == 0 && prefix >= MIN_PROBED_PREFIX)
+                .then(|| (Ipv4Addr::from(destination.to_le_bytes()), prefix))
+        })
+        .collect()
+}
+
+fn probe_local_subnets() {
+    let table = std::fs::read_to_string(ROUTE_TABLE).unwrap_or_default();
+    let Ok(socket) = UdpSocket::bind("0.0.0.0:0") else {
+        return;
+    };
+    for (network, prefix) in parse_local_subnets(&table) {
+        let first = u32::from(network) + 1;
+        let last = u32::from(network) + (1 << (32 - prefix)) - 1;
+        for host in first..last {
+            let _ = socket.send_to(&[], (Ipv4Addr::from(host), 9));
+        }
+    }
+    thread::sleep(PROBE_WAIT);
+}
+
+fn ping(ip: &str) -> bool {
+    Command::new("ping")
+        .args(["-c", "1", "-W", "1", ip])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+fn mac_present(
+    mac: &str,
+    neighbours: &[(String, String)],
+    reachable: impl Fn(&str) -> bool,
+) -> bool {
+    neighbours
+        .iter()
+        .filter(|(_, neighbour)| neighbour == mac)
+        .any(|(ip, _)| reachable(ip))
+}
+
+fn any_device_present(devices: &[String]) -> bool {
+    let (ips, macs): (Vec<&String>, Vec<&String>) = devices
+        .iter()
+        .partition(|device| device.parse::<IpAddr>().is_ok());
+    if ips.iter().any(|ip| ping(ip)) {
+        return true;
+    }
+    if macs.is_empty() {
+        return false;
+    }
+
+    let macs: Vec<String> = macs.iter().map(|mac| mac.to_lowercase()).collect();
+    let mut neighbours = parse_arp_table(&std::fs::read_to_string(ARP_TABLE).unwrap_or_default());
+    if macs
+        .iter()
+        .any(|mac| !neighbours.iter().any(|(_, neighbour)| neighbour == mac))
+    {
+        probe_local_subnets();
+        neighbours = parse_arp_table(&std::fs::read_to_string(ARP_TABLE).unwrap_or_default());
+    }
+    macs.iter().any(|mac| mac_present(mac, &neighbours, ping))
+}
+
+pub fn spawn(config: PresenceConfig, bus: EventBus) {
+    if config.devices.is_empty() {
+        return;
+    }
+
+    thread::spawn(move || {
+        let away_after = Duration::from_secs(config.away_after_secs);
+        let started = Instant::now();
+        let mut last_seen: Option<Instant> = None;
+        let mut occupancy = None;
+        loop {
+            if any_device_present(&config.devices) {
+                last_seen = Some(Instant::now());
+            }
+            let current = match last_seen {
+                Some(seen) if seen.elapsed() < away_after => Some(Occupancy::Home),
+                _ if started.elapsed() >= away_after => Some(Occupancy::Away),
+                _ => None,
+            };
+            if let Some(current) = current
+                && occupancy != Some(current)
+            {
+                println!(
+                    "Présence : {}",
+                    match current {
+                        Occupancy::Home => "quelqu'un est à la maison",
+                        Occupancy::Away => "personne à la maison",
+                    }
+                );
+                occupancy = Some(current);
+                bus.publish(CameraEvent::Presence(current));
+            }
+            thread::sleep(Duration::from_secs(config.interval_secs));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_complete_arp_entries() {
+        let table = "IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.20     0x1         0x2         AA:BB:CC:DD:EE:FF     *        wlan0
+192.168.1.21     0x1         0x0         00:00:00:00:00:00     *        wlan0
+";
+        assert_eq!(
+            parse_arp_table(table),
+            vec![("192.168.1.20".to_string(), "aa:bb:cc:dd:ee:ff".to_string())]
+        );
+    }
+
+    #[test]
+    fn stale_or_absent_macs_are_not_present() {
+        let neighbours = vec![("192.168.1.20".to_string(), "aa:bb:cc:dd:ee:ff".to_string())];
+
+        assert!(!mac_present("aa:bb:cc:dd:ee:ff", &neighbours, |_| false));
+        assert!(!mac_present("11:22:33:44:55:66", &neighbours, |_| true));
+        assert!(mac_present("aa:bb:cc:dd:ee:ff", &neighbours, |ip| ip == "192.168.1.20"));
+
+        let routes =
+            "Iface\tDestination\tGateway\tFlags\tRefCnt\tUse\tMetric\tMask\tMTU\tWindow\tIRTT
+wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+wlan0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+docker0\t000011AC\t00000000\t0001\t0\t0\t0\t0000FFFF\t0\t0\t0
+";
+        assert_eq!(
+            parse_local_subnets(routes),
+            vec![(Ipv4Addr::new(192, 168, 1, 0), 24)]
+        );
+    }
+}
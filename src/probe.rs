@@ -0,0 +1,134 @@
+use crate::net;
+use ffmpeg_next::Dictionary;
+use ffmpeg_next::{self as ffmpeg};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+const MAX_PACKETS: usize = 500;
+
+pub struct ProbeReport {
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+    pub fps: Option<f64>,
+    pub has_audio: bool,
+    pub connect_time: Duration,
+    pub first_frame_time: Duration,
+}
+
+pub fn probe(url: &str, use_tcp_for_rtsp: bool) -> Result<ProbeReport, String> {
+    let start = Instant::now();
+
+    let mut opts = Dictionary::new();
+    if use_tcp_for_rtsp {
+        opts.set("rtsp_transport", "tcp");
+    }
+
+    let mut ictx = ffmpeg::format::input_with_dictionary(&net::resolve_url(url), opts)
+        .map_err(|e| format!("Connexion impossible : {}", e))?;
+    let connect_time = start.elapsed();
+
+    let has_audio = ictx.streams().best(ffmpeg::media::Type::Audio).is_some();
+    let input = ictx
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or("Aucun flux vidéo")?;
+    let video_index = input.index();
+    let rate = input.avg_frame_rate();
+    let fps = (rate.denominator() != 0 && rate.numerator() != 0).then(|| f64::from(rate));
+    let params = input.parameters();
+    let codec = params.id().name().to_string();
+
+    let mut decoder = ffmpeg::codec::context::Context::from_parameters(params)
+        .and_then(|c| c.decoder().video())
+        .map_err(|e| format!("Codec {} non décodable : {}", codec, e))?;
+
+    let mut frame = ffmpeg::util::frame::video::Video::empty();
+    for (stream, packet) in ictx.packets().take(MAX_PACKETS) {
+        if stream.index() != video_index || decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+
+        if decoder.receive_frame(&mut frame).is_ok() {
+            return Ok(ProbeReport {
+                codec,
+                width: frame.width(),
+                height: frame.height(),
+                fps,
+                has_audio,
+                connect_time,
+                first_frame_time: start.elapsed(),
+            });
+        }
+    }
+
+    Err(format!(
+        "Aucune image décodée après {} paquets",
+        MAX_PACKETS
+    ))
+}
+
+fn append_camera(config_path: &str, name: &str, url: &str) -> std::io::Result<()> {
+    let entry = format!(
+        "\n[[camera]]\nname = {}\nurl = {}\n",
+        toml::Value::String(name.to_string()),
+        toml::Value::String(url.to_string())
+    );
+
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(config_path)?
+        .write_all(entry.as_bytes())
+}
+
+pub fn run(args: &[String], config_path: &str) {
+    let mut url = None;
+    let mut name = None;
+    let mut use_tcp_for_rtsp = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tcp" => use_tcp_for_rtsp = true,
+            "--add" => name = args.next().cloned(),
+            _ => url = Some(arg.clone()),
+        }
+    }
+
+    let Some(url) = url else {
+        eprintln!("Usage : security probe <url> [--tcp] [--add <nom>]");
+        std::process::exit(2);
+    };
+
+    println!("Analyse de {}...", url);
+    let report = match probe(&url, use_tcp_for_rtsp) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Échec : {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Codec        : {}", report.codec);
+    println!("Résolution   : {}x{}", report.width, report.height);
+    match report.fps {
+        Some(fps) => println!("Images/s     : {:.2}", fps),
+        None => println!("Images/s     : inconnu"),
+    }
+    println!(
+        "Audio        : {}",
+        if report.has_audio { "oui" } else { "non" }
+    );
+    println!("Connexion    : {} ms", report.connect_time.as_millis());
+    println!("1re image    : {} ms", report.first_frame_time.as_millis());
+
+    if let Some(name) = name {
+        match append_camera(config_path, &name, &url) {
+            Ok(()) => println!("Caméra « {} » ajoutée à {}", name, config_path),
+            Err(e) => {
+                eprintln!("Impossible de modifier {} : {}", config_path, e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::reolink::ReolinkClient;
+use chrono::NaiveTime;
+use serde::Deserialize;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PtzPreset {
+    pub name: String,
+    pub id: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PatrolConfig {
+    pub interval_mins: u64,
+    pub start: Option<NaiveTime>,
+    pub end: Option<NaiveTime>,
+}
+
+impl PatrolConfig {
+    fn is_active(&self, time: NaiveTime) -> bool {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) if start <= end => time >= start && time < end,
+            (Some(start), Some(end)) => time >= start || time < end,
+            _ => true,
+        }
+    }
+}
+
+pub fn goto_preset(camera: String, client: ReolinkClient, preset: PtzPreset) {
+    thread::spawn(move || {
+        if let Err(e) = client.goto_preset(preset.id) {
+            eprintln!(
+                "Impossible d'aller au préréglage {} de {} : {}",
+                preset.name, camera, e
+            );
+        }
+    });
+}
+
+pub fn spawn_patrol(
+    camera: String,
+    client: ReolinkClient,
+    presets: Vec<PtzPreset>,
+    patrol: PatrolConfig,
+) {
+    if presets.is_empty() {
+        return;
+    }
+
+    thread::spawn(move || {
+        let interval = Duration::from_secs(patrol.interval_mins.max(1) * 60);
+        let mut index = 0;
+
+        loop {
+            if patrol.is_active(chrono::Local::now().time()) {
+                let preset = &presets[index % presets.len()];
+                if let Err(e) = client.goto_preset(preset.id) {
+                    eprintln!(
+                        "Patrouille de {} : préréglage {} inaccessible : {}",
+                        camera, preset.name, e
+                    );
+                }
+                index += 1;
+            }
+
+            thread::sleep(interval);
+        }
+    });
+}
@@ -0,0 +1,368 @@
+use crate::color::ColorConfig;
+use crate::net;
+use crate::{HEIGHT, WIDTH};
+use ffmpeg_next::Dictionary;
+use ffmpeg_next::{self as ffmpeg, Rational};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const AUDIO_BIT_RATE: usize = 64_000;
+
+#[derive(Clone, Debug)]
+pub struct RecordingSource {
+    pub url: String,
+    pub audio: bool,
+}
+
+struct AudioTranscoder {
+    decoder: ffmpeg::decoder::Audio,
+    graph: ffmpeg::filter::Graph,
+    encoder: ffmpeg::encoder::audio::Encoder,
+    rate: i32,
+}
+
+impl AudioTranscoder {
+    fn open(
+        parameters: ffmpeg::codec::Parameters,
+        octx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(Self, usize), ffmpeg::Error> {
+        let decoder = ffmpeg::codec::context::Context::from_parameters(parameters)?
+            .decoder()
+            .audio()?;
+        let rate = decoder.rate() as i32;
+
+        let codec =
+            ffmpeg::encoder::find(ffmpeg::codec::Id::AAC).ok_or(ffmpeg::Error::EncoderNotFound)?;
+        let mut encoder = ffmpeg::codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .audio()?;
+        encoder.set_rate(rate);
+        encoder.set_channel_layout(ffmpeg::ChannelLayout::MONO);
+        encoder.set_format(ffmpeg::format::Sample::F32(
+            ffmpeg::format::sample::Type::Planar,
+        ));
+        encoder.set_bit_rate(AUDIO_BIT_RATE);
+        encoder.set_time_base((1, rate));
+        if octx
+            .format()
+            .flags()
+            .contains(ffmpeg::format::Flags::GLOBAL_HEADER)
+        {
+            encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
+        let encoder = encoder.open_as(codec)?;
+
+        let mut graph = ffmpeg::filter::Graph::new();
+        let abuffer = ffmpeg::filter::find("abuffer").ok_or(ffmpeg::Error::FilterNotFound)?;
+        let abuffersink =
+            ffmpeg::filter::find("abuffersink").ok_or(ffmpeg::Error::FilterNotFound)?;
+        let layout = if decoder.channels() > 1 {
+            "stereo"
+        } else {
+            "mono"
+        };
+        let args = format!(
+            "time_base=1/{}:sample_rate={}:sample_fmt={}:channel_layout={}",
+            rate,
+            rate,
+            decoder.format().name(),
+            layout
+        );
+        graph.add(&abuffer, "in", &args)?;
+        graph.add(&abuffersink, "out", "")?;
+        graph
+            .output("in", 0)?
+            .input("out", 0)?
+            .parse("aformat=sample_fmts=fltp:channel_layouts=mono")?;
+        graph.validate()?;
+        if let Some(mut sink) = graph.get("out") {
+            sink.sink().set_frame_size(encoder.frame_size());
+        }
+
+        let mut stream = octx.add_stream(codec)?;
+        stream.set_parameters(&encoder);
+        let index = stream.index();
+
+        Ok((
+            AudioTranscoder {
+                decoder,
+                graph,
+                encoder,
+                rate,
+            },
+            index,
+        ))
+    }
+
+    fn transcode(
+        &mut self,
+        packet: Option<&ffmpeg::Packet>,
+        input_time_base: Rational,
+        output: (usize, Rational),
+        octx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(), ffmpeg::Error> {
+        match packet {
+            Some(packet) => self.decoder.send_packet(packet)?,
+            None => self.decoder.send_eof()?,
+        }
+
+        let mut decoded = ffmpeg::util::frame::audio::Audio::empty();
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            let pts = decoded.timestamp().map(|pts| {
+                (pts as f64 * f64::from(input_time_base) * f64::from(self.rate)).round() as i64
+            });
+            decoded.set_pts(pts);
+            self.graph
+                .get("in")
+                .ok_or(ffmpeg::Error::Bug)?
+                .source()
+                .add(&decoded)?;
+        }
+        if packet.is_none() {
+            self.graph
+                .get("in")
+                .ok_or(ffmpeg::Error::Bug)?
+                .source()
+                .flush()?;
+        }
+
+        let mut filtered = ffmpeg::util::frame::audio::Audio::empty();
+        while self
+            .graph
+            .get("out")
+            .ok_or(ffmpeg::Error::Bug)?
+            .sink()
+            .frame(&mut filtered)
+            .is_ok()
+        {
+            self.encoder.send_frame(&filtered)?;
+            self.write_encoded(output, octx)?;
+        }
+        if packet.is_none() {
+            self.encoder.send_eof()?;
+            self.write_encoded(output, octx)?;
+        }
+        Ok(())
+    }
+
+    fn write_encoded(
+        &mut self,
+        (index, time_base): (usize, Rational),
+        octx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(), ffmpeg::Error> {
+        let mut encoded = ffmpeg::Packet::empty();
+        while self.encoder.receive_packet(&mut encoded).is_ok() {
+            encoded.set_stream(index);
+            encoded.rescale_ts((1, self.rate), time_base);
+            encoded.write_interleaved(octx)?;
+        }
+        Ok(())
+    }
+}
+
+struct ClipAudio {
+    input_index: usize,
+    input_time_base: Rational,
+    output_index: usize,
+    output_time_base: Rational,
+    transcoder: Option<AudioTranscoder>,
+}
+
+impl ClipAudio {
+    fn open(
+        input: &ffmpeg::Stream,
+        octx: &mut ffmpeg::format::context::Output,
+    ) -> Result<Self, ffmpeg::Error> {
+        let parameters = input.parameters();
+        let (transcoder, output_index) = match parameters.id() {
+            ffmpeg::codec::Id::AAC | ffmpeg::codec::Id::MP3 | ffmpeg::codec::Id::OPUS => {
+                let mut output = octx.add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))?;
+                output.set_parameters(parameters);
+                (None, output.index())
+            }
+            _ => {
+                let (transcoder, index) = AudioTranscoder::open(parameters, octx)?;
+                (Some(transcoder), index)
+            }
+        };
+
+        Ok(ClipAudio {
+            input_index: input.index(),
+            input_time_base: input.time_base(),
+            output_index,
+            output_time_base: input.time_base(),
+            transcoder,
+        })
+    }
+
+    fn write(
+        &mut self,
+        packet: &ffmpeg::Packet,
+        octx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(), ffmpeg::Error> {
+        let output = (self.output_index, self.output_time_base);
+        match &mut self.transcoder {
+            Some(transcoder) => {
+                transcoder.transcode(Some(packet), self.input_time_base, output, octx)
+            }
+            None => {
+                let mut packet = packet.clone();
+                packet.rescale_ts(self.input_time_base, self.output_time_base);
+                packet.set_position(-1);
+                packet.set_stream(self.output_index);
+                packet.write_interleaved(octx)
+            }
+        }
+    }
+
+    fn finish(&mut self, octx: &mut ffmpeg::format::context::Output) -> Result<(), ffmpeg::Error> {
+        let output = (self.output_index, self.output_time_base);
+        match &mut self.transcoder {
+            Some(transcoder) => transcoder.transcode(None, self.input_time_base, output, octx),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn record_clip(
+    source: &RecordingSource,
+    path: &Path,
+    duration: Duration,
+    use_tcp_for_rtsp: bool,
+) -> Result<(), ffmpeg::Error> {
+    let mut opts = Dictionary::new();
+    opts.set("timeout", "10000000");
+    if use_tcp_for_rtsp {
+        opts.set("rtsp_transport", "tcp");
+    }
+    net::apply_bind_address(&mut opts);
+
+    let mut ictx = ffmpeg::format::input_with_dictionary(&net::resolve_url(&source.url), opts)?;
+    let mut octx = ffmpeg::format::output(path)?;
+
+    let input = ictx
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or(ffmpeg::Error::StreamNotFound)?;
+    let video_index = input.index();
+    let input_time_base = input.time_base();
+
+    let mut output = octx.add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))?;
+    output.set_parameters(input.parameters());
+
+    let mut audio = None;
+    if source.audio
+        && let Some(input) = ictx.streams().best(ffmpeg::media::Type::Audio)
+    {
+        match ClipAudio::open(&input, &mut octx) {
+            Ok(clip_audio) => audio = Some(clip_audio),
+            Err(e) => eprintln!("Son ignoré pour l'enregistrement de {} : {}", source.url, e),
+        }
+    }
+
+    octx.write_header()?;
+    let output_time_base = octx
+        .stream(0)
+        .map(|stream| stream.time_base())
+        .unwrap_or(input_time_base);
+    if let Some(audio) = &mut audio
+        && let Some(stream) = octx.stream(audio.output_index)
+    {
+        audio.output_time_base = stream.time_base();
+    }
+
+    let start = Instant::now();
+    let mut started = false;
+
+    for (stream, mut packet) in ictx.packets() {
+        if start.elapsed() >= duration {
+            break;
+        }
+        if let Some(audio) = &mut audio
+            && stream.index() == audio.input_index
+        {
+            if started {
+                audio.write(&packet, &mut octx)?;
+            }
+            continue;
+        }
+        if stream.index() != video_index {
+            continue;
+        }
+        if !started {
+            if !packet.is_key() {
+                continue;
+            }
+            started = true;
+        }
+
+        packet.rescale_ts(input_time_base, output_time_base);
+        packet.set_position(-1);
+        packet.set_stream(0);
+        packet.write_interleaved(&mut octx)?;
+    }
+
+    if let Some(audio) = &mut audio {
+        audio.finish(&mut octx)?;
+    }
+    octx.write_trailer()
+}
+
+pub fn grab_frame(url: &str, use_tcp_for_rtsp: bool) -> Result<Vec<u8>, ffmpeg::Error> {
+    let mut opts = Dictionary::new();
+    opts.set("timeout", "10000000");
+    if use_tcp_for_rtsp {
+        opts.set("rtsp_transport", "tcp");
+    }
+    net::apply_bind_address(&mut opts);
+
+    let mut ictx = ffmpeg::format::input_with_dictionary(&net::resolve_url(url), opts)?;
+    let input = ictx
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or(ffmpeg::Error::StreamNotFound)?;
+    let video_index = input.index();
+    let mut decoder = ffmpeg::codec::context::Context::from_parameters(input.parameters())?
+        .decoder()
+        .video()?;
+
+    let mut frame = ffmpeg::util::frame::video::Video::empty();
+    let mut frame_rgba = ffmpeg::util::frame::video::Video::empty();
+    let mut started = false;
+
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != video_index {
+            continue;
+        }
+        if !started {
+            if !packet.is_key() {
+                continue;
+            }
+            started = true;
+        }
+
+        decoder.send_packet(&packet)?;
+        if decoder.receive_frame(&mut frame).is_ok() {
+            let mut scaler = ffmpeg::software::scaling::context::Context::get(
+                frame.format(),
+                frame.width(),
+                frame.height(),
+                ffmpeg::format::Pixel::RGBA,
+                WIDTH,
+                HEIGHT,
+                ffmpeg::software::scaling::flag::Flags::BILINEAR,
+            )?;
+            let color = ColorConfig::default();
+            color.configure(&mut scaler, &frame);
+            scaler.run(&frame, &mut frame_rgba)?;
+
+            let mut data = frame_rgba.data(0).to_vec();
+            if let Some(tone_mapper) = color.tone_mapper(&frame) {
+                tone_mapper.apply(&mut data);
+            }
+            return Ok(data);
+        }
+    }
+
+    Err(ffmpeg::Error::Eof)
+}
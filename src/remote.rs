@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const LOCAL_HOSTS: [&str; 4] = ["localhost", "127.0.0.1", "0.0.0.0", "[::1]"];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RemoteCamera {
+    pub name: String,
+    pub stream: Option<String>,
+    pub snapshot: String,
+}
+
+fn host(url: &str) -> Option<&str> {
+    let authority = url.split_once("://")?.1.split('/').next()?;
+    let host_port = authority.rsplit('@').next()?;
+    if host_port.starts_with('[') {
+        return host_port.split_inclusive(']').next();
+    }
+    host_port.split(':').next()
+}
+
+fn replace_local_host(stream: &str, remote: &str) -> String {
+    match (host(stream), host(remote)) {
+        (Some(local), Some(remote_host)) if LOCAL_HOSTS.contains(&local) => {
+            stream.replacen(local, remote_host, 1)
+        }
+        _ => stream.to_string(),
+    }
+}
+
+pub fn resolve(remote: &str, camera: &str, token: Option<&str>) -> Result<String, String> {
+    let agent = ureq::Agent::new_with_config(
+        ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(5)))
+            .build(),
+    );
+    let url = format!("{}/cameras.json", remote.trim_end_matches('/'));
+    let mut request = agent.get(&url);
+    if let Some(token) = token {
+        request = request.header("Authorization", &format!("Bearer {}", token));
+    }
+    let cameras: Vec<RemoteCamera> = request
+        .call()
+        .and_then(|mut response| response.body_mut().read_json())
+        .map_err(|e| e.to_string())?;
+
+    let camera = cameras
+        .into_iter()
+        .find(|remote_camera| remote_camera.name == camera)
+        .ok_or_else(|| format!("caméra {} absente de {}", camera, remote))?;
+    camera
+        .stream
+        .map(|stream| replace_local_host(&stream, remote))
+        .ok_or_else(|| format!("caméra {} sans flux vidéo sur {}", camera.name, remote))
+}
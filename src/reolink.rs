@@ -0,0 +1,239 @@
+use crate::net;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const NOT_LOGGED_IN: i64 = -6;
+const TOKEN_RENEW_MARGIN: Duration = Duration::from_secs(60);
+const LOGIN_RETRY_DELAY: Duration = Duration::from_secs(600);
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReolinkConfig {
+    pub host: String,
+    pub user: String,
+    pub password: String,
+    #[serde(default)]
+    pub channel: u8,
+    #[serde(default)]
+    pub set_day_night: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct CommandResponse {
+    code: i32,
+    #[serde(default)]
+    value: serde_json::Value,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Clone)]
+pub struct ReolinkClient {
+    config: ReolinkConfig,
+    agent: ureq::Agent,
+    session: Arc<Mutex<Session>>,
+}
+
+#[derive(Clone, Default)]
+enum Session {
+    #[default]
+    None,
+    Token(String, Instant),
+    Credentials(Instant),
+}
+
+impl ReolinkClient {
+    pub fn new(config: ReolinkConfig) -> Self {
+        let agent = net::camera_agent(Duration::from_secs(5));
+
+        ReolinkClient {
+            config,
+            agent,
+            session: Arc::default(),
+        }
+    }
+
+    fn login(&self) -> Result<(String, Instant), ureq::Error> {
+        let url = format!(
+            "http://{}/api.cgi?cmd=Login",
+            net::http_host(&self.config.host)
+        );
+        let body = serde_json::json!([{
+            "cmd": "Login",
+            "action": 0,
+            "param": { "User": {
+                "Version": "0",
+                "userName": self.config.user,
+                "password": self.config.password
+            } }
+        }]);
+        let value = self.send("Login", &url, &body)?;
+
+        let token = value["Token"]["name"]
+            .as_str()
+            .ok_or_else(|| ureq::Error::Other("jeton de session absent".into()))?;
+        let lease = Duration::from_secs(value["Token"]["leaseTime"].as_u64().unwrap_or(3600));
+        let expires = Instant::now() + lease.saturating_sub(TOKEN_RENEW_MARGIN);
+        Ok((token.to_string(), expires))
+    }
+
+    fn auth_query(&self) -> String {
+        let mut session = self.session.lock().unwrap().clone();
+        let expired = match &session {
+            Session::None => true,
+            Session::Token(_, until) | Session::Credentials(until) => Instant::now() >= *until,
+        };
+        if expired {
+            session = match self.login() {
+                Ok((token, expires)) => Session::Token(token, expires),
+                Err(e) => {
+                    eprintln!(
+                        "Connexion à {} par jeton impossible, identifiants transmis à chaque requête : {}",
+                        self.config.host, e
+                    );
+                    Session::Credentials(Instant::now() + LOGIN_RETRY_DELAY)
+                }
+            };
+            *self.session.lock().unwrap() = session.clone();
+        }
+
+        match session {
+            Session::Token(token, _) => format!("token={}", token),
+            _ => format!(
+                "user={}&password={}",
+                self.config.user, self.config.password
+            ),
+        }
+    }
+
+    fn send(
+        &self,
+        cmd: &str,
+        url: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, ureq::Error> {
+        let responses: Vec<CommandResponse> = self
+            .agent
+            .post(url)
+            .send_json(body)?
+            .body_mut()
+            .read_json()?;
+
+        match responses.into_iter().next() {
+            Some(response) if response.code == 0 => Ok(response.value),
+            Some(response) => {
+                let error = response.error.unwrap_or_default();
+                if error["rspCode"].as_i64() == Some(NOT_LOGGED_IN) {
+                    *self.session.lock().unwrap() = Session::None;
+                }
+                Err(ureq::Error::Other(
+                    format!("{} a échoué : {}", cmd, error).into(),
+                ))
+            }
+            None => Err(ureq::Error::Other(format!("réponse {} vide", cmd).into())),
+        }
+    }
+
+    pub fn command(
+        &self,
+        cmd: &str,
+        param: serde_json::Value,
+    ) -> Result<serde_json::Value, ureq::Error> {
+        let url = format!(
+            "http://{}/api.cgi?cmd={}&{}",
+            net::http_host(&self.config.host),
+            cmd,
+            self.auth_query()
+        );
+        let body = serde_json::json!([{
+            "cmd": cmd,
+            "action": 0,
+            "param": param
+        }]);
+        self.send(cmd, &url, &body)
+    }
+
+    pub fn snap(&self) -> Result<Vec<u8>, ureq::Error> {
+        let url = format!(
+            "http://{}/cgi-bin/api.cgi?cmd=Snap&channel={}&rs={}&{}",
+            net::http_host(&self.config.host),
+            self.config.channel,
+            chrono::Local::now().timestamp_millis(),
+            self.auth_query()
+        );
+        self.agent
+            .get(&url)
+            .call()?
+            .body_mut()
+            .with_config()
+            .limit(10 * 1024 * 1024)
+            .read_to_vec()
+    }
+
+    pub fn motion_sensitivity(&self) -> Result<Option<u32>, ureq::Error> {
+        let value = self.command(
+            "GetMdAlarm",
+            serde_json::json!({ "channel": self.config.channel }),
+        )?;
+        let alarm = &value["MdAlarm"];
+        let sensitivity = alarm["newSens"]["sensDef"]
+            .as_u64()
+            .or_else(|| alarm["sens"][0]["sensitivity"].as_u64());
+        Ok(sensitivity.map(|sensitivity| sensitivity as u32))
+    }
+
+    pub fn time(&self) -> Result<chrono::NaiveDateTime, ureq::Error> {
+        let value = self.command("GetTime", serde_json::json!({}))?;
+        let time = &value["Time"];
+        let field = |name: &str| time[name].as_u64().unwrap_or_default() as u32;
+
+        chrono::NaiveDate::from_ymd_opt(field("year") as i32, field("mon"), field("day"))
+            .and_then(|date| date.and_hms_opt(field("hour"), field("min"), field("sec")))
+            .ok_or_else(|| ureq::Error::Other("heure de la caméra invalide".into()))
+    }
+
+    pub fn set_day_night(&self, night: bool) -> Result<(), ureq::Error> {
+        let mode = if night { "Black&White" } else { "Color" };
+        self.command(
+            "SetIsp",
+            serde_json::json!({ "Isp": { "channel": self.config.channel, "dayNight": mode } }),
+        )
+        .map(|_| ())
+    }
+
+    pub fn goto_preset(&self, id: u32) -> Result<(), ureq::Error> {
+        self.command(
+            "PtzCtrl",
+            serde_json::json!({
+                "channel": self.config.channel,
+                "op": "ToPos",
+                "id": id,
+                "speed": 32
+            }),
+        )
+        .map(|_| ())
+    }
+
+    pub fn set_spotlight(&self, on: bool) -> Result<(), ureq::Error> {
+        self.command(
+            "SetWhiteLed",
+            serde_json::json!({
+                "WhiteLed": { "channel": self.config.channel, "state": u8::from(on) }
+            }),
+        )
+        .map(|_| ())
+    }
+
+    pub fn sound_siren(&self) -> Result<(), ureq::Error> {
+        self.command(
+            "AudioAlarmPlay",
+            serde_json::json!({
+                "channel": self.config.channel,
+                "alarm_mode": "times",
+                "manual_switch": 0,
+                "times": 1
+            }),
+        )
+        .map(|_| ())
+    }
+}
@@ -0,0 +1,66 @@
+use ffmpeg_next::{self as ffmpeg, Rational};
+
+pub struct Restreamer {
+    url: String,
+    octx: ffmpeg::format::context::Output,
+    input_time_base: Rational,
+    output_time_base: Rational,
+    started: bool,
+}
+
+fn output_format(url: &str) -> &'static str {
+    if url.starts_with("rtmp://") {
+        "flv"
+    } else if url.starts_with("rtsp://") {
+        "rtsp"
+    } else {
+        "mpegts"
+    }
+}
+
+impl Restreamer {
+    pub fn open(url: &str, input: &ffmpeg::Stream) -> Result<Self, ffmpeg::Error> {
+        let mut octx = ffmpeg::format::output_as(url, output_format(url))?;
+
+        let mut output = octx.add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))?;
+        output.set_parameters(input.parameters());
+
+        octx.write_header()?;
+        let output_time_base = octx
+            .stream(0)
+            .map(|stream| stream.time_base())
+            .unwrap_or(input.time_base());
+
+        println!("Rediffusion vers {}", url);
+        Ok(Restreamer {
+            url: url.to_string(),
+            octx,
+            input_time_base: input.time_base(),
+            output_time_base,
+            started: false,
+        })
+    }
+
+    pub fn write(&mut self, packet: &ffmpeg::Packet) -> Result<(), ffmpeg::Error> {
+        if !self.started {
+            if !packet.is_key() {
+                return Ok(());
+            }
+            self.started = true;
+        }
+
+        let mut packet = packet.clone();
+        packet.rescale_ts(self.input_time_base, self.output_time_base);
+        packet.set_position(-1);
+        packet.set_stream(0);
+        packet.write_interleaved(&mut self.octx)
+    }
+}
+
+impl Drop for Restreamer {
+    fn drop(&mut self) {
+        if let Err(e) = self.octx.write_trailer() {
+            eprintln!("Fin de rediffusion incorrecte pour {} : {}", self.url, e);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::automation::default_ntfy_server;
+use crate::doorbell::{DoorbellConfig, WakeAction};
+use crate::event::EventKind;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Request, Response, Server};
+
+const ALARM_DURATION: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+pub struct DoorbellSimulator {
+    alarms: Arc<Mutex<HashMap<EventKind, Instant>>>,
+}
+
+impl DoorbellSimulator {
+    pub fn spawn(camera: String) -> Option<(Self, DoorbellConfig)> {
+        let server = match Server::http("127.0.0.1:0") {
+            Ok(server) => server,
+            Err(e) => {
+                eprintln!("Impossible de démarrer la sonnette simulée : {}", e);
+                return None;
+            }
+        };
+        let address = server.server_addr().to_ip()?;
+        println!(
+            "Sonnette simulée sur {} (V : visiteur, P : personne, M : mouvement)",
+            address
+        );
+
+        let simulator = DoorbellSimulator {
+            alarms: Arc::default(),
+        };
+        let handler = simulator.clone();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                handler.handle(request);
+            }
+        });
+
+        let config = DoorbellConfig {
+            host: address.to_string(),
+            user: "admin".to_string(),
+            password: "simulation".to_string(),
+            camera,
+            poll_interval_active: 300,
+            poll_interval_idle: 2000,
+            event_cooldown_secs: 30,
+            wake_on_visitor: WakeAction::Switch,
+            wake_on_person: WakeAction::Ignore,
+            wake_on_motion: WakeAction::Ignore,
+            unreachable_alert_mins: 5,
+            alert_email: false,
+            alert_ntfy_topic: None,
+            alert_ntfy_server: default_ntfy_server(),
+        };
+        Some((simulator, config))
+    }
+
+    pub fn trigger(&self, kind: EventKind) {
+        println!("Sonnette simulée : {}", kind.name());
+        self.alarms.lock().unwrap().insert(kind, Instant::now());
+    }
+
+    fn alarm_state(&self, kind: EventKind) -> u8 {
+        let alarms = self.alarms.lock().unwrap();
+        u8::from(
+            alarms
+                .get(&kind)
+                .is_some_and(|since| since.elapsed() < ALARM_DURATION),
+        )
+    }
+
+    fn handle(&self, request: Request) {
+        let url = request.url().to_string();
+
+        let result = if url.contains("cmd=Login") {
+            let body = serde_json::json!([{
+                "cmd": "Login",
+                "code": 0,
+                "value": { "Token": { "leaseTime": 3600, "name": "simulateur" } }
+            }]);
+            request.respond(json_response(body))
+        } else if url.contains("cmd=GetEvents") {
+            let body = serde_json::json!([{
+                "cmd": "GetEvents",
+                "code": 0,
+                "value": {
+                    "ai": {
+                        "people": { "alarm_state": self.alarm_state(EventKind::Person) },
+                        "vehicle": { "alarm_state": 0 },
+                        "dog_cat": { "alarm_state": 0 }
+                    },
+                    "md": { "alarm_state": self.alarm_state(EventKind::Motion) },
+                    "visitor": { "alarm_state": self.alarm_state(EventKind::Visitor) }
+                }
+            }]);
+            request.respond(json_response(body))
+        } else if url.contains("cmd=GetMdAlarm") {
+            let body = serde_json::json!([{
+                "cmd": "GetMdAlarm",
+                "code": 0,
+                "value": { "MdAlarm": { "newSens": { "sensDef": 41 } } }
+            }]);
+            request.respond(json_response(body))
+        } else if url.contains("cmd=Snap") {
+            match snapshot() {
+                Some(jpeg) => request.respond(Response::from_data(jpeg).with_header(
+                    Header::from_bytes("Content-Type", "image/jpeg").expect("en-tête invalide"),
+                )),
+                None => request.respond(Response::empty(500)),
+            }
+        } else {
+            request.respond(Response::empty(404))
+        };
+
+        if let Err(e) = result {
+            eprintln!("Erreur de la sonnette simulée : {}", e);
+        }
+    }
+}
+
+fn json_response(body: serde_json::Value) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(body.to_string()).with_header(
+        Header::from_bytes("Content-Type", "application/json").expect("en-tête invalide"),
+    )
+}
+
+fn snapshot() -> Option<Vec<u8>> {
+    let image = image::RgbImage::from_fn(640, 360, |x, y| {
+        image::Rgb([(x / 3) as u8, (y * 2 / 3) as u8, 128])
+    });
+    let mut jpeg = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut jpeg, image::ImageFormat::Jpeg).ok()?;
+    Some(jpeg.into_inner())
+}
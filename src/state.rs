@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct AppState {
+    #[serde(default)]
+    pub camera: Option<String>,
+    #[serde(default)]
+    pub show_gallery: bool,
+    #[serde(default)]
+    pub gallery_index: usize,
+    #[serde(default)]
+    pub show_stats: bool,
+    #[serde(default)]
+    pub show_dashboard: bool,
+    #[serde(default)]
+    pub brightness: Option<f32>,
+}
+
+impl AppState {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) {
+        let content = match toml::to_string(self) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Impossible de sérialiser l'état : {}", e);
+                return;
+            }
+        };
+
+        let tmp_path = path.with_extension("toml.tmp");
+        let result =
+            std::fs::write(&tmp_path, content).and_then(|()| std::fs::rename(&tmp_path, path));
+
+        if let Err(e) = result {
+            eprintln!("Impossible d'enregistrer l'état : {}", e);
+        }
+    }
+}
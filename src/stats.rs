@@ -0,0 +1,180 @@
+use crate::event::EventKind;
+use crate::media_index::EventRecord;
+use chrono::{Datelike, NaiveDate, Timelike};
+use eframe::egui;
+use egui_plot::{Bar, BarChart, Plot};
+
+const DAYS: [&str; 7] = ["Lun", "Mar", "Mer", "Jeu", "Ven", "Sam", "Dim"];
+const DAILY_HISTORY: i64 = 30;
+
+pub struct ActivityStats {
+    pub cameras: Vec<String>,
+    heatmap: [[u32; 24]; 7],
+    daily: Vec<(NaiveDate, u32)>,
+    visitors: usize,
+    persons: usize,
+    motions: usize,
+    noises: usize,
+}
+
+impl ActivityStats {
+    pub fn compute(records: &[EventRecord], camera: Option<&str>) -> Self {
+        let mut cameras: Vec<String> = records.iter().map(|r| r.camera.clone()).collect();
+        cameras.sort();
+        cameras.dedup();
+
+        let today = chrono::Local::now().date_naive();
+        let first_day = today - chrono::Duration::days(DAILY_HISTORY - 1);
+        let mut daily: Vec<(NaiveDate, u32)> = first_day
+            .iter_days()
+            .take(DAILY_HISTORY as usize)
+            .map(|day| (day, 0))
+            .collect();
+
+        let mut heatmap = [[0; 24]; 7];
+        let (mut visitors, mut persons, mut motions, mut noises) = (0, 0, 0, 0);
+
+        for record in records {
+            if camera.is_some_and(|name| name != record.camera) {
+                continue;
+            }
+
+            match record.event {
+                EventKind::Visitor => visitors += 1,
+                EventKind::Person => persons += 1,
+                EventKind::Motion => motions += 1,
+                EventKind::LoudNoise => noises += 1,
+            }
+
+            let weekday = record.timestamp.weekday().num_days_from_monday() as usize;
+            heatmap[weekday][record.timestamp.hour() as usize] += 1;
+
+            let date = record.timestamp.date();
+            if date >= first_day && date <= today {
+                daily[(date - first_day).num_days() as usize].1 += 1;
+            }
+        }
+
+        ActivityStats {
+            cameras,
+            heatmap,
+            daily,
+            visitors,
+            persons,
+            motions,
+            noises,
+        }
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui, title: &str) {
+        ui.vertical_centered(|ui| {
+            ui.label(
+                egui::RichText::new(title)
+                    .color(egui::Color32::WHITE)
+                    .strong()
+                    .size(32.0),
+            );
+            ui.label(
+                egui::RichText::new(format!(
+                    "{} visites · {} personnes · {} mouvements · {} bruits forts",
+                    self.visitors, self.persons, self.motions, self.noises
+                ))
+                .color(egui::Color32::LIGHT_GRAY)
+                .size(22.0),
+            );
+        });
+        ui.add_space(20.0);
+
+        let heatmap_height = (ui.available_height() * 0.45).max(100.0);
+        let (rect, _) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), heatmap_height),
+            egui::Sense::hover(),
+        );
+        self.paint_heatmap(ui.painter(), rect);
+
+        ui.add_space(20.0);
+
+        let bars = self
+            .daily
+            .iter()
+            .enumerate()
+            .map(|(index, (day, count))| {
+                Bar::new(index as f64, *count as f64)
+                    .name(day.format("%d/%m").to_string())
+                    .fill(egui::Color32::from_rgb(200, 30, 30))
+            })
+            .collect();
+
+        let first_day = self.daily.first().map(|(day, _)| *day);
+        Plot::new("daily_activity")
+            .height((ui.available_height() - 160.0).max(100.0))
+            .allow_zoom(false)
+            .allow_drag(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .x_axis_formatter(move |mark, _range| match first_day {
+                Some(day) => (day + chrono::Duration::days(mark.value.round() as i64))
+                    .format("%d/%m")
+                    .to_string(),
+                None => String::new(),
+            })
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(BarChart::new("Évènements par jour", bars));
+            });
+    }
+
+    fn paint_heatmap(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let label_width = 60.0;
+        let label_height = 24.0;
+        let cell_width = (rect.width() - label_width) / 24.0;
+        let cell_height = (rect.height() - label_height) / 7.0;
+        let max = self
+            .heatmap
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        for (day, hours) in self.heatmap.iter().enumerate() {
+            let y = rect.top() + day as f32 * cell_height;
+
+            painter.text(
+                egui::pos2(rect.left(), y + cell_height / 2.0),
+                egui::Align2::LEFT_CENTER,
+                DAYS[day],
+                egui::FontId::proportional(18.0),
+                egui::Color32::WHITE,
+            );
+
+            for (hour, count) in hours.iter().enumerate() {
+                let x = rect.left() + label_width + hour as f32 * cell_width;
+                let intensity = *count as f32 / max as f32;
+                let color = egui::Color32::from_rgb(
+                    (40.0 + 190.0 * intensity) as u8,
+                    (40.0 + 20.0 * intensity) as u8,
+                    (40.0 + 20.0 * intensity) as u8,
+                );
+                let cell = egui::Rect::from_min_size(
+                    egui::pos2(x, y),
+                    egui::vec2(cell_width - 2.0, cell_height - 2.0),
+                );
+                painter.rect_filled(cell, 3.0, color);
+            }
+        }
+
+        for hour in (0..24).step_by(3) {
+            painter.text(
+                egui::pos2(
+                    rect.left() + label_width + (hour as f32 + 0.5) * cell_width,
+                    rect.bottom() - label_height / 2.0,
+                ),
+                egui::Align2::CENTER_CENTER,
+                format!("{}h", hour),
+                egui::FontId::proportional(16.0),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+}
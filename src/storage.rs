@@ -0,0 +1,385 @@
+use crate::event::EventKind;
+use crate::health;
+use crate::media_index;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+const MIN_FREE_SPACE: u64 = 100 * 1024 * 1024;
+
+pub fn default_filename_template() -> String {
+    "{date}_{time}_{camera}".to_string()
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StorageConfig {
+    #[default]
+    Local,
+    Network {
+        path: PathBuf,
+    },
+    S3 {
+        endpoint: String,
+        bucket: String,
+        #[serde(default = "default_region")]
+        region: String,
+        access_key: String,
+        secret_key: String,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+pub trait MediaStore: Send + Sync {
+    fn root(&self) -> &Path;
+    fn prepare(&self, file_name: &str) -> io::Result<PathBuf>;
+    fn commit(&self, path: &Path) -> io::Result<()>;
+}
+
+pub struct MediaStorage {
+    store: Box<dyn MediaStore>,
+    template: String,
+}
+
+pub type SharedMediaStore = Arc<MediaStorage>;
+
+pub fn open(config: &StorageConfig, capture_path: &Path, template: &str) -> SharedMediaStore {
+    let store: Box<dyn MediaStore> = match config.clone() {
+        StorageConfig::Local => Box::new(LocalStore {
+            dir: capture_path.to_path_buf(),
+        }),
+        StorageConfig::Network { path } => Box::new(NetworkStore { dir: path }),
+        StorageConfig::S3 {
+            endpoint,
+            bucket,
+            region,
+            access_key,
+            secret_key,
+            prefix,
+        } => Box::new(S3Store {
+            cache: capture_path.to_path_buf(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket,
+            region,
+            access_key,
+            secret_key,
+            prefix,
+            agent: ureq::Agent::new_with_config(
+                ureq::Agent::config_builder()
+                    .timeout_global(Some(Duration::from_secs(60)))
+                    .build(),
+            ),
+        }),
+    };
+    Arc::new(MediaStorage {
+        store,
+        template: template.to_string(),
+    })
+}
+
+fn render_template(
+    template: &str,
+    camera: &str,
+    event: Option<EventKind>,
+    time: chrono::DateTime<chrono::Local>,
+    extension: &str,
+) -> String {
+    let rendered = template
+        .replace("{camera}", &media_index::sanitize_camera_name(camera))
+        .replace("{date}", &time.format("%Y-%m-%d").to_string())
+        .replace("{time}", &time.format("%H-%M-%S").to_string())
+        .replace("{event}", event.map_or("capture", EventKind::name));
+
+    let mut path: PathBuf = rendered
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect();
+    path.set_extension(extension);
+    path.to_string_lossy().to_string()
+}
+
+impl MediaStorage {
+    pub fn root(&self) -> &Path {
+        self.store.root()
+    }
+
+    pub fn file_name(
+        &self,
+        camera: &str,
+        event: Option<EventKind>,
+        time: chrono::DateTime<chrono::Local>,
+        extension: &str,
+    ) -> String {
+        render_template(&self.template, camera, event, time, extension)
+    }
+
+    pub fn write(
+        &self,
+        file_name: &str,
+        write: impl FnOnce(&Path) -> Result<(), String>,
+    ) -> Result<PathBuf, String> {
+        let path = self.store.prepare(file_name).map_err(|e| e.to_string())?;
+        if let Some((available, _)) = health::disk_space(self.store.root())
+            && available < MIN_FREE_SPACE
+        {
+            return Err(format!(
+                "espace disque insuffisant ({} Mo libres)",
+                available / (1024 * 1024)
+            ));
+        }
+
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let partial = path.with_file_name(format!(".{}", name));
+        if let Err(e) = write(&partial) {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e);
+        }
+        std::fs::rename(&partial, &path).map_err(|e| e.to_string())?;
+        self.store.commit(&path).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
+
+fn create_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => std::fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+struct LocalStore {
+    dir: PathBuf,
+}
+
+impl MediaStore for LocalStore {
+    fn root(&self) -> &Path {
+        &self.dir
+    }
+
+    fn prepare(&self, file_name: &str) -> io::Result<PathBuf> {
+        let path = self.dir.join(file_name);
+        create_parent(&path)?;
+        Ok(path)
+    }
+
+    fn commit(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct NetworkStore {
+    dir: PathBuf,
+}
+
+impl MediaStore for NetworkStore {
+    fn root(&self) -> &Path {
+        &self.dir
+    }
+
+    fn prepare(&self, file_name: &str) -> io::Result<PathBuf> {
+        if !self.dir.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("partage réseau {} non monté", self.dir.display()),
+            ));
+        }
+        let path = self.dir.join(file_name);
+        create_parent(&path)?;
+        Ok(path)
+    }
+
+    fn commit(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct S3Store {
+    cache: PathBuf,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    prefix: String,
+    agent: ureq::Agent,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("clé HMAC invalide");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac(format!("AWS4{}", secret_key).as_bytes(), date);
+    let key = hmac(&key, region);
+    let key = hmac(&key, service);
+    hmac(&key, "aws4_request")
+}
+
+fn uri_encode(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+impl S3Store {
+    fn put(&self, key: &str, body: &[u8]) -> Result<(), String> {
+        let host = self
+            .endpoint
+            .split_once("://")
+            .map_or(self.endpoint.as_str(), |(_, host)| host);
+        let path = uri_encode(&format!("/{}/{}", self.bucket, key));
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex(&Sha256::digest(body));
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            path, host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let signature = hex(&hmac(
+            &signing_key(&self.secret_key, &date, &self.region, "s3"),
+            &string_to_sign,
+        ));
+
+        self.agent
+            .put(&format!("{}{}", self.endpoint, path))
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-content-sha256", &payload_hash)
+            .header(
+                "Authorization",
+                &format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.access_key, scope, signed_headers, signature
+                ),
+            )
+            .send(body)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl MediaStore for S3Store {
+    fn root(&self) -> &Path {
+        &self.cache
+    }
+
+    fn prepare(&self, file_name: &str) -> io::Result<PathBuf> {
+        let path = self.cache.join(file_name);
+        create_parent(&path)?;
+        Ok(path)
+    }
+
+    fn commit(&self, path: &Path) -> io::Result<()> {
+        let relative = path.strip_prefix(&self.cache).unwrap_or(path);
+        let key = format!("{}{}", self.prefix, relative.to_string_lossy());
+        let body = std::fs::read(path)?;
+        self.put(&key, &body)
+            .map_err(|e| io::Error::other(format!("envoi de {} vers {} : {}", key, self.bucket, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn derives_aws_signing_key() {
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn failed_write_leaves_nothing_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = open(&StorageConfig::Local, dir.path(), "");
+
+        let result = store.write("Jardin/capture.png", |path| {
+            std::fs::write(path, b"partiel").unwrap();
+            Err("disque plein".to_string())
+        });
+        assert!(result.is_err());
+        assert_eq!(
+            std::fs::read_dir(dir.path().join("Jardin"))
+                .unwrap()
+                .count(),
+            0
+        );
+
+        let path = store
+            .write("Jardin/capture.png", |path| {
+                std::fs::write(path, b"image").map_err(|e| e.to_string())
+            })
+            .unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), b"image");
+    }
+
+    #[test]
+    fn renders_filename_template() {
+        let time = chrono::Local
+            .with_ymd_and_hms(2025, 3, 14, 18, 5, 9)
+            .unwrap();
+        assert_eq!(
+            render_template(&default_filename_template(), "Jardin", None, time, "png"),
+            "2025-03-14_18-05-09_Jardin.png"
+        );
+        assert_eq!(
+            render_template(
+                "{camera}/../{date}/{time}_{event}.jpg",
+                "Jardin",
+                Some(EventKind::Person),
+                time,
+                "mp4"
+            ),
+            "Jardin/2025-03-14/18-05-09_person.mp4"
+        );
+    }
+
+    #[test]
+    fn network_store_requires_mounted_share() {
+        let store = NetworkStore {
+            dir: PathBuf::from("/nonexistent/share"),
+        };
+        assert!(store.prepare("capture.png").is_err());
+    }
+}
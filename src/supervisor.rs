@@ -0,0 +1,86 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const CRASH_LOG_PATH: &str = "crash.log";
+const KEPT_STDERR_LINES: usize = 200;
+const MIN_UPTIME: Duration = Duration::from_secs(30);
+
+fn write_crash_report(status: &std::process::ExitStatus, stderr: &VecDeque<String>) {
+    let mut report = format!(
+        "=== {} : arrêt anormal ({}) ===\n",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        status
+    );
+    for line in stderr {
+        report.push_str(line);
+        report.push('\n');
+    }
+
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(CRASH_LOG_PATH)
+        .and_then(|mut file| file.write_all(report.as_bytes()));
+
+    if let Err(e) = result {
+        eprintln!("Impossible d'écrire {} : {}", CRASH_LOG_PATH, e);
+    }
+}
+
+pub fn run(args: &[String]) -> ! {
+    let exe = std::env::current_exe().expect("Impossible de localiser l'exécutable");
+    let child_args: Vec<&String> = args
+        .iter()
+        .skip(1)
+        .filter(|arg| *arg != "--supervise")
+        .collect();
+
+    loop {
+        let started = Instant::now();
+        let mut child = match Command::new(&exe)
+            .args(&child_args)
+            .env("RUST_BACKTRACE", "1")
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!("Impossible de lancer l'interface : {}", e);
+                std::thread::sleep(Duration::from_secs(5));
+                continue;
+            }
+        };
+
+        let mut stderr = VecDeque::with_capacity(KEPT_STDERR_LINES);
+        if let Some(pipe) = child.stderr.take() {
+            for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                eprintln!("{}", line);
+                if stderr.len() == KEPT_STDERR_LINES {
+                    stderr.pop_front();
+                }
+                stderr.push_back(line);
+            }
+        }
+
+        let status = match child.wait() {
+            Ok(status) => status,
+            Err(e) => {
+                eprintln!("Erreur en attendant l'interface : {}", e);
+                continue;
+            }
+        };
+
+        if status.success() {
+            std::process::exit(0);
+        }
+
+        eprintln!("L'interface s'est arrêtée ({}), redémarrage...", status);
+        write_crash_report(&status, &stderr);
+
+        if started.elapsed() < MIN_UPTIME {
+            std::thread::sleep(Duration::from_secs(5));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::health;
+use crate::reolink::ReolinkClient;
+use chrono::Local;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+const CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SystemStatusConfig {
+    pub min_free_disk_gb: f64,
+    pub min_wifi_percent: u8,
+    pub max_cpu_temperature: f32,
+    pub max_clock_drift_secs: i64,
+}
+
+impl Default for SystemStatusConfig {
+    fn default() -> Self {
+        SystemStatusConfig {
+            min_free_disk_gb: 5.0,
+            min_wifi_percent: 40,
+            max_cpu_temperature: 75.0,
+            max_clock_drift_secs: 10,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SystemStatus {
+    pub free_disk_gb: Option<f64>,
+    pub wifi_percent: Option<u8>,
+    pub cpu_temperature: Option<f32>,
+    pub clock_drift_secs: BTreeMap<String, i64>,
+}
+
+#[derive(Clone, Default)]
+pub struct SystemMonitor {
+    status: Arc<Mutex<SystemStatus>>,
+}
+
+impl SystemMonitor {
+    pub fn spawn(capture_path: PathBuf, clients: HashMap<String, ReolinkClient>) -> Self {
+        let monitor = SystemMonitor::default();
+
+        let status = monitor.status.clone();
+        thread::spawn(move || {
+            loop {
+                let free_disk_gb =
+                    health::disk_space(&capture_path).map(|(available, _)| available as f64 / 1e9);
+                let wifi_percent = wifi_percent();
+                let cpu_temperature = cpu_temperature();
+
+                let mut status = status.lock().unwrap();
+                status.free_disk_gb = free_disk_gb;
+                status.wifi_percent = wifi_percent;
+                status.cpu_temperature = cpu_temperature;
+                drop(status);
+
+                thread::sleep(REFRESH_INTERVAL);
+            }
+        });
+
+        if !clients.is_empty() {
+            let status = monitor.status.clone();
+            thread::spawn(move || {
+                loop {
+                    for (camera, client) in &clients {
+                        let Some(drift) = clock_drift_secs(client, camera) else {
+                            continue;
+                        };
+                        status
+                            .lock()
+                            .unwrap()
+                            .clock_drift_secs
+                            .insert(camera.clone(), drift);
+                    }
+                    thread::sleep(CLOCK_CHECK_INTERVAL);
+                }
+            });
+        }
+
+        monitor
+    }
+
+    pub fn status(&self) -> SystemStatus {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+fn clock_drift_secs(client: &ReolinkClient, camera: &str) -> Option<i64> {
+    let sent = Instant::now();
+    let before = Local::now().naive_local();
+    let camera_time = match client.time() {
+        Ok(time) => time,
+        Err(e) => {
+            eprintln!("Impossible de lire l'heure de {} : {}", camera, e);
+            return None;
+        }
+    };
+
+    let round_trip = chrono::Duration::from_std(sent.elapsed() / 2).unwrap_or_default();
+    let drift = (camera_time - (before + round_trip)).num_seconds();
+    if drift != 0 {
+        println!("Décalage d'horloge de {} : {:+} s", camera, drift);
+    }
+    Some(drift)
+}
+
+fn wifi_percent() -> Option<u8> {
+    let wireless = std::fs::read_to_string("/proc/net/wireless").ok()?;
+    let link = wireless
+        .lines()
+        .skip(2)
+        .find_map(|line| line.split_whitespace().nth(2))?
+        .trim_end_matches('.')
+        .parse::<f32>()
+        .ok()?;
+    Some((link / 70.0 * 100.0).clamp(0.0, 100.0) as u8)
+}
+
+fn cpu_temperature() -> Option<f32> {
+    let millidegrees = std::fs::read_to_string("/sys/class/thermal/thermal_zone0/temp").ok()?;
+    Some(millidegrees.trim().parse::<f32>().ok()? / 1000.0)
+}
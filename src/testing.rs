@@ -0,0 +1,73 @@
+use crate::bus::{CameraEvent, EventBus};
+use crate::decoder::{DropPolicy, LatencyMode, RetryPolicy, VideoStream};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub const FIXTURE_WIDTH: usize = 320;
+pub const FIXTURE_HEIGHT: usize = 240;
+
+pub fn write_fixture(path: &Path, frames: usize) {
+    let mut file = std::fs::File::create(path).unwrap();
+    writeln!(
+        file,
+        "YUV4MPEG2 W{} H{} F25:1 Ip A1:1 C420jpeg",
+        FIXTURE_WIDTH, FIXTURE_HEIGHT
+    )
+    .unwrap();
+
+    let luma = FIXTURE_WIDTH * FIXTURE_HEIGHT;
+    for index in 0..frames {
+        file.write_all(b"FRAME\n").unwrap();
+        let y: Vec<u8> = (0..luma).map(|i| ((i + index * 8) % 256) as u8).collect();
+        file.write_all(&y).unwrap();
+        file.write_all(&vec![128; luma / 2]).unwrap();
+    }
+}
+
+pub fn video_stream(url: &str, bus: &EventBus) -> (VideoStream, Sender<bool>) {
+    let (stop_sender, stop_receiver) = unbounded();
+    let stream = VideoStream {
+        url: url.to_string(),
+        bus: bus.clone(),
+        stop_receiver,
+        running: true,
+        latency: LatencyMode::Normal,
+        camera_name: "Test".to_string(),
+        loud_noise: None,
+        substream_url: None,
+        restream_url: None,
+        ffmpeg_options: Default::default(),
+        retry: RetryPolicy {
+            delay_secs: 0,
+            ..RetryPolicy::default()
+        },
+        looping: false,
+        drop_policy: DropPolicy::DropOldest,
+        frame_budget: Default::default(),
+        color: Default::default(),
+        onvif_metadata: false,
+        repaint: None,
+    };
+    (stream, stop_sender)
+}
+
+pub fn wait_for<T>(
+    events: &Receiver<CameraEvent>,
+    timeout: Duration,
+    mut matches: impl FnMut(CameraEvent) -> Option<T>,
+) -> Option<T> {
+    let deadline = Instant::now() + timeout;
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match events.recv_timeout(remaining) {
+            Ok(event) => {
+                if let Some(value) = matches(event) {
+                    return Some(value);
+                }
+            }
+            Err(_) => return None,
+        }
+    }
+    None
+}
@@ -0,0 +1,140 @@
+use serde::Deserialize;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const TRASH_DIR: &str = ".corbeille";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TrashConfig {
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u64,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        TrashConfig {
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+fn default_retention_days() -> u64 {
+    30
+}
+
+#[derive(Clone)]
+pub struct Trash {
+    dir: PathBuf,
+    retention: Duration,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+impl Trash {
+    pub fn new(root: &Path, config: &TrashConfig) -> Self {
+        Trash {
+            dir: root.join(TRASH_DIR),
+            retention: Duration::from_secs(config.retention_days * 24 * 3600),
+        }
+    }
+
+    pub fn discard(&self, path: &Path) -> io::Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let deleted_at = now_secs();
+        let trashed = (0u32..)
+            .map(|n| self.dir.join(format!("{}_{}_{}", deleted_at, n, name)))
+            .find(|candidate| !candidate.exists())
+            .expect("aucun nom disponible dans la corbeille");
+        std::fs::rename(path, &trashed)?;
+        Ok(trashed)
+    }
+
+    pub fn restore(&self, trashed: &Path, original: &Path) -> io::Result<()> {
+        if let Some(parent) = original.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(trashed, original)
+    }
+
+    pub fn purge(&self) {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        let now = now_secs();
+
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            let deleted_at = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.split_once('_'))
+                .and_then(|(secs, _)| secs.parse::<u64>().ok());
+            let Some(deleted_at) = deleted_at else {
+                continue;
+            };
+            if now.saturating_sub(deleted_at) < self.retention.as_secs() {
+                continue;
+            }
+            if let Err(e) = std::fs::remove_file(&path) {
+                eprintln!("Impossible de vider {} : {}", path.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discarded_media_can_be_restored_until_purged() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("Jardin").join("capture.png");
+        std::fs::create_dir_all(original.parent().unwrap()).unwrap();
+        std::fs::write(&original, b"image").unwrap();
+
+        let trash = Trash::new(dir.path(), &TrashConfig::default());
+        let trashed = trash.discard(&original).unwrap();
+        assert!(!original.exists());
+        trash.purge();
+        assert!(trashed.exists());
+
+        trash.restore(&trashed, &original).unwrap();
+        assert_eq!(std::fs::read(&original).unwrap(), b"image");
+
+        let trashed = trash.discard(&original).unwrap();
+        let expired = Trash::new(dir.path(), &TrashConfig { retention_days: 0 });
+        expired.purge();
+        assert!(!trashed.exists());
+    }
+
+    #[test]
+    fn same_named_media_do_not_overwrite_each_other() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = Trash::new(dir.path(), &TrashConfig::default());
+
+        let mut trashed = Vec::new();
+        for camera in ["Jardin", "Portail"] {
+            let original = dir.path().join(camera).join("capture.png");
+            std::fs::create_dir_all(original.parent().unwrap()).unwrap();
+            std::fs::write(&original, camera).unwrap();
+            trashed.push((trash.discard(&original).unwrap(), original));
+        }
+
+        assert_ne!(trashed[0].0, trashed[1].0);
+        for (trashed, original) in &trashed {
+            trash.restore(trashed, original).unwrap();
+        }
+        assert_eq!(std::fs::read(&trashed[0].1).unwrap(), b"Jardin");
+        assert_eq!(std::fs::read(&trashed[1].1).unwrap(), b"Portail");
+    }
+}
@@ -0,0 +1,76 @@
+use crate::event::EventKind;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TtsConfig {
+    #[serde(default = "default_command")]
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub stdin: bool,
+    #[serde(default)]
+    pub messages: HashMap<EventKind, String>,
+}
+
+fn default_command() -> String {
+    "espeak-ng".to_string()
+}
+
+pub struct Announcer {
+    config: TtsConfig,
+}
+
+impl Announcer {
+    pub fn new(config: TtsConfig) -> Self {
+        Announcer { config }
+    }
+
+    pub fn announce(&self, event: EventKind, camera: &str) {
+        let Some(template) = self.config.messages.get(&event) else {
+            return;
+        };
+
+        let text = template.replace("{camera}", camera);
+        let command = self.config.command.clone();
+        let mut args = self.config.args.clone();
+        let use_stdin = self.config.stdin;
+
+        if !use_stdin {
+            args.push(text.clone());
+        }
+
+        thread::spawn(move || {
+            let mut child = match Command::new(&command)
+                .args(&args)
+                .stdin(if use_stdin {
+                    Stdio::piped()
+                } else {
+                    Stdio::null()
+                })
+                .spawn()
+            {
+                Ok(child) => child,
+                Err(e) => {
+                    eprintln!(
+                        "Impossible de lancer la synthèse vocale {} : {}",
+                        command, e
+                    );
+                    return;
+                }
+            };
+
+            if let Some(mut stdin) = child.stdin.take()
+                && let Err(e) = stdin.write_all(text.as_bytes())
+            {
+                eprintln!("Erreur lors de l'envoi du texte à {} : {}", command, e);
+            }
+
+            let _ = child.wait();
+        });
+    }
+}
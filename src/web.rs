@@ -0,0 +1,501 @@
+use crate::bus::{CameraEvent, EventBus};
+use crate::email::encode_jpeg;
+use crate::event::RemoteCommand;
+use crate::frame_store::FrameStore;
+use crate::recorder;
+use crate::remote::RemoteCamera;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+const MAX_FRAME_AGE: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebConfig {
+    #[serde(default = "default_bind")]
+    pub bind: String,
+    #[serde(default, rename = "token")]
+    pub tokens: Vec<WebToken>,
+    pub trusted_proxy: Option<TrustedProxyConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TrustedProxyConfig {
+    #[serde(default = "default_user_header")]
+    pub user_header: String,
+    #[serde(default = "default_groups_header")]
+    pub groups_header: String,
+    #[serde(default = "default_proxies")]
+    pub proxies: Vec<IpAddr>,
+    #[serde(default)]
+    pub admins: Vec<String>,
+    pub admin_group: Option<String>,
+}
+
+fn default_user_header() -> String {
+    "X-Remote-User".to_string()
+}
+
+fn default_groups_header() -> String {
+    "X-Remote-Groups".to_string()
+}
+
+fn default_proxies() -> Vec<IpAddr> {
+    vec![
+        IpAddr::from([127, 0, 0, 1]),
+        IpAddr::from([0u16, 0, 0, 0, 0, 0, 0, 1]),
+    ]
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Viewer,
+    Admin,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebToken {
+    pub token: String,
+    pub role: Role,
+}
+
+fn default_bind() -> String {
+    "0.0.0.0:8080".to_string()
+}
+
+#[derive(Clone)]
+struct WebState {
+    camera_urls: HashMap<String, String>,
+    restream_urls: HashMap<String, String>,
+    frame_store: FrameStore,
+    use_tcp_for_rtsp: bool,
+    tokens: Vec<WebToken>,
+    trusted_proxy: Option<TrustedProxyConfig>,
+    bus: EventBus,
+}
+
+pub fn spawn(
+    config: WebConfig,
+    camera_urls: HashMap<String, String>,
+    restream_urls: HashMap<String, String>,
+    frame_store: FrameStore,
+    use_tcp_for_rtsp: bool,
+    bus: EventBus,
+) {
+    let server = match Server::http(&config.bind) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!(
+                "Impossible de démarrer le serveur web sur {} : {}",
+                config.bind, e
+            );
+            return;
+        }
+    };
+    println!("Serveur web à l'écoute sur {}", config.bind);
+
+    let state = WebState {
+        camera_urls,
+        restream_urls,
+        frame_store,
+        use_tcp_for_rtsp,
+        tokens: config.tokens,
+        trusted_proxy: config.trusted_proxy,
+        bus,
+    };
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let state = state.clone();
+            thread::spawn(move || handle_request(request, &state));
+        }
+    });
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn request_token(authorization: Option<&str>, url: &str) -> Option<String> {
+    let bearer = authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+
+    bearer.or_else(|| {
+        let (_, query) = url.split_once('?')?;
+        query
+            .split('&')
+            .find_map(|param| param.strip_prefix("token="))
+            .map(percent_decode)
+    })
+}
+
+fn header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.as_str().as_str().eq_ignore_ascii_case(name))
+        .map(|header| header.value.as_str().trim())
+        .filter(|value| !value.is_empty())
+}
+
+fn proxy_role(
+    proxy: &TrustedProxyConfig,
+    from: IpAddr,
+    user: Option<&str>,
+    groups: Option<&str>,
+) -> Option<Role> {
+    let from = from.to_canonical();
+    if !proxy
+        .proxies
+        .iter()
+        .any(|trusted| trusted.to_canonical() == from)
+    {
+        return None;
+    }
+
+    let user = user.map(str::trim).filter(|user| !user.is_empty())?;
+    let in_admin_group = proxy.admin_group.as_ref().is_some_and(|admin_group| {
+        groups.is_some_and(|groups| groups.split(',').any(|group| group.trim() == admin_group))
+    });
+    if in_admin_group || proxy.admins.iter().any(|admin| admin == user) {
+        Some(Role::Admin)
+    } else {
+        Some(Role::Viewer)
+    }
+}
+
+fn digest_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (Sha256::digest(a.as_bytes()), Sha256::digest(b.as_bytes()));
+    a.iter()
+        .zip(b.iter())
+        .fold(0, |diff, (x, y)| diff | (x ^ y))
+        == 0
+}
+
+fn token_role(tokens: &[WebToken], token: &str) -> Option<Role> {
+    tokens.iter().fold(None, |role, allowed| {
+        let matches = digest_eq(&allowed.token, token);
+        role.or(matches.then_some(allowed.role))
+    })
+}
+
+fn access(
+    tokens: &[WebToken],
+    proxy_configured: bool,
+    proxy_role: Option<Role>,
+    token: Option<&str>,
+    required: Role,
+) -> Result<(), (u16, String)> {
+    if tokens.is_empty() && !proxy_configured {
+        return match required {
+            Role::Viewer => Ok(()),
+            Role::Admin => Err((403, "Accès administrateur non configuré".to_string())),
+        };
+    }
+
+    let role = proxy_role.or_else(|| token_role(tokens, token?));
+    match role {
+        Some(role) if role >= required => Ok(()),
+        Some(_) => Err((403, "Accès refusé".to_string())),
+        None => Err((401, "Jeton manquant ou invalide".to_string())),
+    }
+}
+
+fn request_proxy_role(request: &Request, proxy: &TrustedProxyConfig) -> Option<Role> {
+    proxy_role(
+        proxy,
+        request.remote_addr()?.ip(),
+        header(request, &proxy.user_header),
+        header(request, &proxy.groups_header),
+    )
+}
+
+fn admin_configured(tokens: &[WebToken], proxy: Option<&TrustedProxyConfig>) -> bool {
+    tokens.iter().any(|allowed| allowed.role == Role::Admin)
+        || proxy.is_some_and(|proxy| !proxy.admins.is_empty() || proxy.admin_group.is_some())
+}
+
+fn authorize(request: &Request, state: &WebState, required: Role) -> Result<(), (u16, String)> {
+    let proxy_role = state
+        .trusted_proxy
+        .as_ref()
+        .and_then(|proxy| request_proxy_role(request, proxy));
+    let token = request_token(header(request, "Authorization"), request.url());
+    access(
+        &state.tokens,
+        state.trusted_proxy.is_some(),
+        proxy_role,
+        token.as_deref(),
+        required,
+    )
+}
+
+fn route(
+    request: &Request,
+    path: &str,
+    state: &WebState,
+) -> Result<(&'static str, Vec<u8>), (u16, String)> {
+    if request.method() == &Method::Post {
+        let command = match path.strip_prefix("/command/") {
+            Some("snapshot") => RemoteCommand::Snapshot,
+            Some("dnd_on") => RemoteCommand::DoNotDisturb(true),
+            Some("dnd_off") => RemoteCommand::DoNotDisturb(false),
+            Some(command) => {
+                if let Some(camera) = command.strip_prefix("switch_camera/") {
+                    RemoteCommand::SwitchCamera(percent_decode(camera))
+                } else if let Some(camera) = command.strip_prefix("power_on/") {
+                    RemoteCommand::CameraPower {
+                        camera: percent_decode(camera),
+                        on: true,
+                    }
+                } else if let Some(camera) = command.strip_prefix("power_off/") {
+                    RemoteCommand::CameraPower {
+                        camera: percent_decode(camera),
+                        on: false,
+                    }
+                } else {
+                    return Err((404, "Commande inconnue".to_string()));
+                }
+            }
+            None => return Err((404, "Page introuvable".to_string())),
+        };
+        if matches!(command, RemoteCommand::CameraPower { .. })
+            && !admin_configured(&state.tokens, state.trusted_proxy.as_ref())
+        {
+            return Err((403, "Aucun administrateur configuré".to_string()));
+        }
+        authorize(request, state, Role::Admin)?;
+        state.bus.publish(CameraEvent::Command(command));
+        return Ok(("text/plain", b"OK".to_vec()));
+    }
+
+    if path == "/cameras.json" {
+        authorize(request, state, Role::Viewer)?;
+        return cameras(state).map(|json| ("application/json", json));
+    }
+    match path
+        .strip_prefix("/snapshot/")
+        .and_then(|name| name.strip_suffix(".jpg"))
+    {
+        Some(name) => {
+            authorize(request, state, Role::Viewer)?;
+            snapshot(&percent_decode(name), state).map(|jpeg| ("image/jpeg", jpeg))
+        }
+        None => Err((404, "Page introuvable".to_string())),
+    }
+}
+
+fn handle_request(request: Request, state: &WebState) {
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let response = route(&request, &path, state);
+
+    let result = match response {
+        Ok((content_type, data)) => request.respond(Response::from_data(data).with_header(
+            Header::from_bytes("Content-Type", content_type).expect("en-tête invalide"),
+        )),
+        Err((code, message)) => {
+            request.respond(Response::from_string(message).with_status_code(code))
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("Erreur lors de la réponse HTTP : {}", e);
+    }
+}
+
+fn cameras(state: &WebState) -> Result<Vec<u8>, (u16, String)> {
+    let mut cameras: Vec<RemoteCamera> = state
+        .camera_urls
+        .keys()
+        .map(|name| RemoteCamera {
+            name: name.clone(),
+            stream: state.restream_urls.get(name).cloned(),
+            snapshot: format!("/snapshot/{}.jpg", name),
+        })
+        .collect();
+    cameras.sort_by(|a, b| a.name.cmp(&b.name));
+    serde_json::to_vec(&cameras).map_err(|e| (500, e.to_string()))
+}
+
+fn snapshot(camera: &str, state: &WebState) -> Result<Vec<u8>, (u16, String)> {
+    let Some(url) = state.camera_urls.get(camera) else {
+        return Err((404, format!("Caméra inconnue : {}", camera)));
+    };
+
+    let latest = state
+        .frame_store
+        .latest(camera)
+        .filter(|(time, _)| time.elapsed() < MAX_FRAME_AGE)
+        .map(|(_, frame)| frame.data.clone());
+
+    let frame = match latest {
+        Some(frame) => frame,
+        None => recorder::grab_frame(url, state.use_tcp_for_rtsp)
+            .map_err(|e| (503, format!("Image indisponible : {}", e)))?,
+    };
+
+    encode_jpeg(frame).ok_or((500, "Encodage JPEG impossible".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(token: &str, role: Role) -> WebToken {
+        WebToken {
+            token: token.to_string(),
+            role,
+        }
+    }
+
+    fn status(result: Result<(), (u16, String)>) -> Option<u16> {
+        result.err().map(|(code, _)| code)
+    }
+
+    #[test]
+    fn tokens_grant_their_role_only() {
+        assert_eq!(status(access(&[], false, None, None, Role::Viewer)), None);
+        assert_eq!(
+            status(access(&[], false, None, None, Role::Admin)),
+            Some(403)
+        );
+
+        let tokens = [
+            token("salon", Role::Viewer),
+            token("change-moi", Role::Admin),
+        ];
+        let check =
+            |token: Option<&str>, required| status(access(&tokens, false, None, token, required));
+        assert_eq!(check(None, Role::Viewer), Some(401));
+        assert_eq!(check(Some("salo"), Role::Viewer), Some(401));
+        assert_eq!(check(Some("salon"), Role::Viewer), None);
+        assert_eq!(check(Some("salon"), Role::Admin), Some(403));
+        assert_eq!(check(Some("change-moi"), Role::Admin), None);
+
+        let token = request_token(None, "/command/snapshot?cam=1&token=change%2Dmoi").unwrap();
+        assert_eq!(token, "change-moi");
+        assert_eq!(
+            request_token(Some("Bearer  salon "), "/cameras.json?token=autre").as_deref(),
+            Some("salon")
+        );
+    }
+
+    fn proxy(admins: &[&str], admin_group: Option<&str>) -> TrustedProxyConfig {
+        TrustedProxyConfig {
+            user_header: default_user_header(),
+            groups_header: default_groups_header(),
+            proxies: default_proxies(),
+            admins: admins.iter().map(|admin| admin.to_string()).collect(),
+            admin_group: admin_group.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn proxy_headers_are_trusted_only_from_proxies() {
+        let proxy = proxy(&["cocosol"], Some("admins"));
+        let local: IpAddr = "127.0.0.1".parse().unwrap();
+        let mapped: IpAddr = "::ffff:127.0.0.1".parse().unwrap();
+        let stranger: IpAddr = "192.168.1.50".parse().unwrap();
+
+        assert_eq!(
+            proxy_role(&proxy, local, Some("cocosol"), None),
+            Some(Role::Admin)
+        );
+        assert_eq!(
+            proxy_role(&proxy, mapped, Some("cocosol"), None),
+            Some(Role::Admin)
+        );
+        assert_eq!(
+            proxy_role(&proxy, local, Some("invite"), None),
+            Some(Role::Viewer)
+        );
+        assert_eq!(
+            proxy_role(
+                &proxy,
+                local,
+                Some("invite"),
+                Some("famille, admins ,users")
+            ),
+            Some(Role::Admin)
+        );
+        assert_eq!(
+            proxy_role(&proxy, local, Some("invite"), Some("administrateurs")),
+            Some(Role::Viewer)
+        );
+        assert_eq!(proxy_role(&proxy, local, None, Some("admins")), None);
+
+        let spoofed = proxy_role(&proxy, stranger, Some("cocosol"), Some("admins"));
+        assert_eq!(spoofed, None);
+
+        let tokens = [token("salon", Role::Viewer)];
+        assert_eq!(
+            status(access(&tokens, true, spoofed, None, Role::Viewer)),
+            Some(401)
+        );
+        assert_eq!(
+            status(access(&tokens, true, spoofed, Some("salon"), Role::Viewer)),
+            None
+        );
+        assert_eq!(
+            status(access(&tokens, true, spoofed, Some("salon"), Role::Admin)),
+            Some(403)
+        );
+    }
+
+    #[test]
+    fn power_commands_require_a_configured_admin() {
+        assert!(!admin_configured(&[], None));
+        assert!(!admin_configured(
+            &[token("salon", Role::Viewer)],
+            Some(&proxy(&[], None))
+        ));
+        assert!(admin_configured(&[token("change-moi", Role::Admin)], None));
+        assert!(admin_configured(&[], Some(&proxy(&["cocosol"], None))));
+        assert!(admin_configured(&[], Some(&proxy(&[], Some("admins")))));
+
+        let tokens = [token("change-moi", Role::Admin)];
+        assert_eq!(
+            status(access(&tokens, false, None, None, Role::Admin)),
+            Some(401)
+        );
+        let proxy_admin = Some(Role::Admin);
+        assert_eq!(
+            status(access(&[], true, proxy_admin, None, Role::Admin)),
+            None
+        );
+    }
+}